@@ -1,20 +1,76 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use fakeit::beer;
-use tuiscope::FuzzyFinder;
+use std::time::{Duration, Instant};
+use tui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+use tuiscope::{Algorithm, FuzzyFinder, FuzzyList};
 
-fn set_filter(c: &mut Criterion) {
+fn options() -> Vec<String> {
     let mut options = Vec::<String>::new();
     for _ in 1..1_000_000 {
         options.push(beer::name());
     }
-    let mut fuzzy_finder = FuzzyFinder::default();
+    options
+}
+
+fn algorithms() -> Vec<(&'static str, Algorithm)> {
+    vec![
+        ("skim", Algorithm::SkimV2),
+        #[cfg(feature = "nucleo")]
+        ("nucleo", Algorithm::Nucleo),
+    ]
+}
+
+fn set_filter(c: &mut Criterion) {
+    let options = options();
+    let mut group = c.benchmark_group("score 1,000,000");
+    for (name, algorithm) in algorithms() {
+        let mut fuzzy_finder = FuzzyFinder::with_capacity(options.len());
+        fuzzy_finder.set_algorithm(algorithm);
+        fuzzy_finder.push_options(&options);
+        // alternate filters, setting an unchanged filter is a no-op.
+        let filters = ["a", "b"];
+        let mut i = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                i ^= 1;
+                fuzzy_finder.set_filter(black_box(filters[i]));
+            })
+        });
+    }
+    group.finish();
+}
+
+fn clear_filter(c: &mut Criterion) {
+    let options = options();
+    let mut fuzzy_finder = FuzzyFinder::with_capacity(options.len());
     fuzzy_finder.push_options(&options);
-    c.bench_function("score 1,000,000", |b| {
-        b.iter(|| {
-            fuzzy_finder.set_filter(black_box("b".to_string()));
+    c.bench_function("clear 1,000,000", |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                fuzzy_finder.set_filter("b");
+                let start = Instant::now();
+                fuzzy_finder.clear_filter();
+                elapsed += start.elapsed();
+            }
+            elapsed
         })
     });
 }
 
-criterion_group!(benches, set_filter);
+fn render_deep_selection(c: &mut Criterion) {
+    let options = options();
+    let mut fuzzy_finder = FuzzyFinder::with_capacity(options.len());
+    fuzzy_finder.push_options(&options);
+    for _ in 0..100_000 {
+        fuzzy_finder.select_next();
+    }
+    let area = Rect::new(0, 0, 80, 24);
+    let mut buf = Buffer::empty(area);
+    c.bench_function("render selection 100,000", |b| {
+        b.iter(|| FuzzyList::default().render(area, &mut buf, &mut fuzzy_finder))
+    });
+}
+
+criterion_group!(benches, set_filter, clear_filter, render_deep_selection);
 criterion_main!(benches);
@@ -31,8 +31,8 @@ struct App<'a> {
 
 impl<'a> App<'a> {
     fn submit_message(&mut self) {
-        if let Some(selection) = self.fuzzy_finder.selection() {
-            self.messages.push(selection.value.to_string());
+        if let Some((_, selection)) = self.fuzzy_finder.commit() {
+            self.messages.push(selection);
         }
         self.input.reset();
         self.fuzzy_finder.clear_filter();
@@ -2,6 +2,8 @@
 /// where stdin provides the options, and the user types to filter, using up/down to
 /// select a choice.  Then pressing Enter prints the choice to stdout and the program
 /// exits.
+///
+/// Pass `--ansi` to keep the colors of input such as `ls --color=always`.
 use crossterm::{
     event::{
         DisableMouseCapture, EnableMouseCapture, Event::Key, EventStream, KeyCode, KeyEvent,
@@ -19,14 +21,7 @@ use tokio::{sync::mpsc::Sender, task::JoinHandle};
 use tracing::error;
 use tui::{prelude::*, widgets::*};
 use tui_input::{backend::crossterm::EventHandler, Input};
-use tuiscope::{FuzzyFinder, FuzzyList};
-
-#[derive(Default)]
-enum AppState {
-    #[default]
-    Reading,
-    Ready,
-}
+use tuiscope::{FuzzyFinder, FuzzyList, Sanitizer};
 
 /// App holds the state of the application
 #[derive(Default)]
@@ -35,20 +30,20 @@ struct App<'a> {
     input: Input,
     /// Fuzzy Finder
     pub fuzzy_finder: FuzzyFinder<'a>,
-    state: AppState,
 }
 
 impl<'a> App<'a> {
     pub fn selection(&self) -> Result<String> {
         Ok(self
             .fuzzy_finder
-            .selection()
-            .map(|s| s.value.to_string())
+            .commit()
+            .map(|(_, value)| value)
             .unwrap_or_default())
     }
     pub fn handle_key(&mut self, key: &crossterm::event::Event) {
         self.input.handle_event(key);
-        self.fuzzy_finder.set_filter(self.input.to_string());
+        self.fuzzy_finder
+            .set_filter_in_background(self.input.to_string());
     }
 }
 
@@ -128,20 +123,32 @@ async fn main() -> Result<()> {
 }
 
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>) -> Result<String> {
-    let mut app = App::default();
+    // lines from stdin are owned by the finder, so it needs no backing store.
+    let mut app: App<'static> = App::default();
 
     let (tx, mut rx) = channel::<Event>(20);
     tick_task(tx.clone()).await?;
     crossterm_event_task(tx.clone()).await?;
     stdin_task(tx).await?;
 
+    let sanitizer = if std::env::args().any(|arg| arg == "--ansi") {
+        Sanitizer::AnsiStyled
+    } else {
+        Sanitizer::ControlChars
+    };
+    app.fuzzy_finder.set_sanitizer(sanitizer);
+
+    // rescore streamed lines once a tick rather than once per line.
+    app.fuzzy_finder.set_loading(true).begin_batch();
+
     loop {
+        app.fuzzy_finder.poll_results();
         terminal.draw(|f| ui(f, &mut app))?;
 
         if let Some(event) = rx.recv().await {
             match event {
                 Event::EOF => {
-                    app.state = AppState::Ready;
+                    app.fuzzy_finder.set_loading(false).end_batch();
                 }
                 Event::NewLine(line) => {
                     app.fuzzy_finder.push_option(line);
@@ -165,7 +172,9 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>) -> Result<String> {
                         }
                     }
                 }
-                Event::Tick => {}
+                Event::Tick => {
+                    app.fuzzy_finder.update();
+                }
             }
         }
     }
@@ -182,13 +191,16 @@ fn ui(f: &mut Frame<'_>, app: &mut App) {
         .block(Block::default().borders(Borders::ALL).title("Filter"));
     f.render_widget(input, chunks[0]);
 
-    let results_title = match app.state {
-        AppState::Reading => "[Loading] Options",
-        AppState::Ready => "Options",
+    let empty_message = if app.fuzzy_finder.is_empty() {
+        "No Options"
+    } else {
+        "No Matches"
     };
     let fuzzy_results = FuzzyList::default()
         .matched_char_style(Style::default().fg(Color::Cyan))
-        .block(Block::default().borders(Borders::ALL).title(results_title))
+        .block(Block::default().borders(Borders::ALL).title("Options"))
+        .empty_message(empty_message)
+        .loading_indicator("[Loading]")
         .selection_highlight_style(Style::default().add_modifier(Modifier::BOLD));
     f.render_stateful_widget(fuzzy_results, chunks[1], &mut app.fuzzy_finder);
 }
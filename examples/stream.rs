@@ -0,0 +1,162 @@
+/// This example walks the current directory on a background thread, streaming the
+/// paths it finds into the finder as owned `String`s while the user types to filter,
+/// using up/down to select a path.  Then pressing Enter prints the choice to stdout
+/// and the program exits.
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    error::Error,
+    fs, io,
+    path::Path,
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+    thread,
+    time::Duration,
+};
+use tui::{prelude::*, widgets::*};
+use tui_input::{backend::crossterm::EventHandler, Input};
+use tuiscope::{FuzzyFinder, FuzzyList};
+
+/// App holds the state of the application
+#[derive(Default)]
+struct App {
+    /// Current value of the input box
+    input: Input,
+    /// Fuzzy Finder, which owns the streamed paths, so needs no backing store
+    pub fuzzy_finder: FuzzyFinder<'static>,
+}
+
+impl App {
+    /// Pushes every path found since the last call, rescoring once for them all.
+    /// Returns false once the walk has finished.
+    fn receive_paths(&mut self, rx: &Receiver<String>) -> bool {
+        let mut paths = Vec::new();
+        let walking = loop {
+            match rx.try_recv() {
+                Ok(path) => paths.push(path),
+                Err(TryRecvError::Empty) => break true,
+                Err(TryRecvError::Disconnected) => break false,
+            }
+        };
+        self.fuzzy_finder.push_options(paths);
+        walking
+    }
+
+    pub fn handle_key(&mut self, key: &crossterm::event::Event) {
+        self.input.handle_event(key);
+        self.fuzzy_finder.set_filter(self.input.to_string());
+    }
+}
+
+/// Sends the paths of files under `dir` to `tx`, stopping early if it hangs up.
+fn walk(dir: &Path, tx: &Sender<String>) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return true;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let more = if path.is_dir() {
+            walk(&path, tx)
+        } else {
+            tx.send(path.display().to_string()).is_ok()
+        };
+        if !more {
+            return false;
+        }
+    }
+    true
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let (tx, rx) = channel();
+    thread::spawn(move || walk(Path::new("."), &tx));
+
+    // setup terminal
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    // create app and run it
+    let res = run_app(&mut terminal, App::default(), &rx);
+
+    // restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+
+    match res {
+        Err(err) => println!("{err:?}"),
+        Ok(Some(path)) => println!("{path}"),
+        Ok(None) => {}
+    }
+
+    Ok(())
+}
+
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    rx: &Receiver<String>,
+) -> io::Result<Option<String>> {
+    app.fuzzy_finder.set_loading(true);
+    loop {
+        if app.fuzzy_finder.is_loading() && !app.receive_paths(rx) {
+            app.fuzzy_finder.set_loading(false);
+        }
+        terminal.draw(|f| ui(f, &mut app))?;
+
+        // don't block on input, so paths keep streaming in while the user is idle.
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => {
+                    return Ok(app.fuzzy_finder.commit().map(|(_, path)| path));
+                }
+                KeyCode::Up => {
+                    app.fuzzy_finder.select_prev();
+                }
+                KeyCode::Down => {
+                    app.fuzzy_finder.select_next();
+                }
+                KeyCode::Esc => return Ok(None),
+                _ => {
+                    app.handle_key(&Event::Key(key));
+                }
+            }
+        }
+    }
+}
+
+fn ui(f: &mut Frame<'_>, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+        .split(f.size());
+
+    let input = Paragraph::new(app.input.to_string())
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().borders(Borders::ALL).title("Filter"));
+    f.render_widget(input, chunks[0]);
+
+    let title = format!("Files ({})", app.fuzzy_finder.len());
+    let fuzzy_results = FuzzyList::default()
+        .matched_char_style(Style::default().fg(Color::Cyan))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .empty_message("No Matches")
+        .loading_indicator("[Walking]")
+        .selection_highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(fuzzy_results, chunks[1], &mut app.fuzzy_finder);
+}
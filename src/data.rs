@@ -1,15 +1,50 @@
+use crate::highlight::render_marked;
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
-use indexmap::IndexMap;
-use rayon::prelude::*;
-use std::{borrow::Cow, cmp::Ordering};
-use tui::widgets::ListState;
+use indexmap::{map::Entry, IndexMap};
+use rayon::{prelude::*, ThreadPool};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt, iter,
+    ops::Range,
+    path::{is_separator, Path},
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::ListState,
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 
-/// Type for holding fuzzy match score with corresponding indices
+/// Errors updating a `FuzzyFinder`'s options.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    #[error("{count} duplicate options would be collapsed, the first being {first:?}")]
+    Duplicates { count: usize, first: String },
+}
+
+/// Type for holding fuzzy match score with corresponding indices.
+///
+/// Ordered by relevance, so ascending order is highest score first.  External widgets
+/// can sort by this to stay consistent with `FuzzyList`.
 pub struct FuzzyScore {
     /// fuzzy match score
     pub score: i64,
     /// fuzzy match indices (positions in the matched string)
     pub indices: Vec<usize>,
+    /// which of the option's fields matched, 0 being the option's value
+    pub field: usize,
+    /// match quality in `0.0..=1.0`, see `match_quality`
+    pub quality: f64,
 }
 
 impl Ord for FuzzyScore {
@@ -33,15 +68,1059 @@ impl PartialEq for FuzzyScore {
 
 impl Eq for FuzzyScore {}
 
+/// Scores `value` against `filter` with `matcher`, `None` if it doesn't match.  This
+/// is the scoring `FuzzyFinder` applies in `MatchMode::Fuzzy`.
+///
+/// # Example
+///
+/// ```
+/// use tuiscope::{fuzzy_matcher::skim::SkimMatcherV2, score};
+///
+/// let matcher = SkimMatcherV2::default();
+/// let fuzzy_score = score(&matcher, "hello", "heo").unwrap();
+/// assert_eq!(fuzzy_score.indices, vec![0, 1, 4]);
+/// assert!(score(&matcher, "hello", "z").is_none());
+/// ```
+pub fn score<M: FuzzyMatcher + ?Sized>(
+    matcher: &M,
+    value: &str,
+    filter: &str,
+) -> Option<FuzzyScore> {
+    matcher
+        .fuzzy_indices(value, filter)
+        .map(|(score, indices)| FuzzyScore {
+            score,
+            quality: match_quality(&indices, value),
+            indices,
+            field: 0,
+        })
+}
+
+/// A token of a filter in `MatchMode::Extended`'s syntax, see
+/// `FuzzyFinder::filter_tokens`.  Tokens other than fuzzy ones are smart case, only
+/// matching case sensitively if they contain uppercase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterToken<'a> {
+    /// matched fuzzily, e.g. `sbtrkt`
+    Fuzzy(&'a str),
+    /// contained exactly, marked with a leading `'`, e.g. `'wild`
+    Exact(&'a str),
+    /// starts the option, marked with a leading `^`, e.g. `^music`
+    Prefix(&'a str),
+    /// ends the option, marked with a trailing `$`, e.g. `.mp3$`
+    Suffix(&'a str),
+    /// must not be contained, marked with a leading `!`, e.g. `!fire`
+    Exclude(&'a str),
+}
+
+impl<'a> FilterToken<'a> {
+    /// Parses a whitespace free `token`.  A marker alone is matched fuzzily as is.
+    fn parse(token: &'a str) -> Self {
+        let marked = |text: Option<&'a str>| text.filter(|text| !text.is_empty());
+        if let Some(text) = marked(token.strip_prefix('!')) {
+            Self::Exclude(text)
+        } else if let Some(text) = marked(token.strip_prefix('\'')) {
+            Self::Exact(text)
+        } else if let Some(text) = marked(token.strip_prefix('^')) {
+            Self::Prefix(text)
+        } else if let Some(text) = marked(token.strip_suffix('$')) {
+            Self::Suffix(text)
+        } else {
+            Self::Fuzzy(token)
+        }
+    }
+}
+
+/// Tokens of `filter` in `MatchMode::Extended`'s syntax.
+fn filter_tokens(filter: &str) -> impl Iterator<Item = FilterToken<'_>> {
+    filter.split_whitespace().map(FilterToken::parse)
+}
+
+/// Score per char of a token matched exactly in `MatchMode::Extended`, roughly skim's
+/// score for a char in a contiguous run.
+const EXACT_CHAR_SCORE: i64 = 16;
+
+/// Scores `value` against `filter` in `MatchMode::Extended`'s syntax, `None` if any
+/// token rules it out.  Scores and indices of the tokens are combined.
+fn score_extended(matcher: &dyn FuzzyMatcher, value: &str, filter: &str) -> Option<FuzzyScore> {
+    let mut total = 0;
+    let mut indices = Vec::new();
+    let mut lowercase = None;
+    for token in filter_tokens(filter) {
+        let text = match token {
+            FilterToken::Fuzzy(text) => {
+                let fuzzy = score(matcher, value, text)?;
+                total += fuzzy.score;
+                indices.extend(fuzzy.indices);
+                continue;
+            }
+            FilterToken::Exact(text)
+            | FilterToken::Prefix(text)
+            | FilterToken::Suffix(text)
+            | FilterToken::Exclude(text) => text,
+        };
+        // smart case, as skim is.
+        let haystack = if text.chars().any(char::is_uppercase) {
+            value
+        } else {
+            lowercase.get_or_insert_with(|| value.to_lowercase())
+        };
+        let offset = match token {
+            FilterToken::Exclude(_) if haystack.contains(text) => return None,
+            FilterToken::Exclude(_) | FilterToken::Fuzzy(_) => continue,
+            FilterToken::Exact(_) => haystack.find(text)?,
+            FilterToken::Prefix(_) => haystack.starts_with(text).then_some(0)?,
+            FilterToken::Suffix(_) => haystack
+                .ends_with(text)
+                .then(|| haystack.len() - text.len())?,
+        };
+        let start = haystack[..offset].chars().count();
+        let len = text.chars().count();
+        total += EXACT_CHAR_SCORE * i64::try_from(len).unwrap_or(i64::MAX / EXACT_CHAR_SCORE);
+        indices.extend(start..start + len);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Some(FuzzyScore {
+        score: total,
+        quality: match_quality(&indices, value),
+        indices,
+        field: 0,
+    })
+}
+
+/// Options scored at a time when scoring lazily, see `FuzzyFinder::set_lazy_scoring`.
+const LAZY_CHUNK: usize = 4096;
+
+/// Score added per filter char matched as an initial in `MatchMode::Acronym`.
+const ACRONYM_BONUS: i64 = 100;
+
+/// Char indices of the initials of words and `camelCase` humps in `value` matching
+/// the chars of `filter` in order, ignoring case and whitespace in `filter`, `None`
+/// if they don't all match.
+fn acronym_indices(value: &str, filter: &str) -> Option<Vec<usize>> {
+    let mut wanted = filter.chars().filter(|c| !c.is_whitespace()).peekable();
+    wanted.peek()?;
+    let mut indices = Vec::new();
+    let mut previous: Option<char> = None;
+    for (index, c) in value.chars().enumerate() {
+        let initial = c.is_alphanumeric()
+            && previous.map_or(true, |previous| {
+                !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+            });
+        previous = Some(c);
+        if initial
+            && wanted
+                .next_if(|w| w.to_lowercase().eq(c.to_lowercase()))
+                .is_some()
+        {
+            indices.push(index);
+            if wanted.peek().is_none() {
+                return Some(indices);
+            }
+        }
+    }
+    None
+}
+
+/// Quality of a match of the chars at `indices` in `value`, in `0.0..=1.0`, e.g. to
+/// color code results without depending on the matcher's score scale.
+///
+/// The formula is `(matched / len) * (matched / span)`, where `matched` is the number
+/// of matched chars, `len` the number of chars in `value`, and `span` the number of
+/// chars from the first match to the last inclusive.  So matching all of `value`
+/// scores 1.0, and a match scores lower the more of `value` it leaves unmatched, or
+/// the more it's spread out.  Matching nothing, e.g. with an empty filter, scores 0.0.
+///
+/// # Example
+///
+/// ```
+/// use tuiscope::match_quality;
+///
+/// assert_eq!(match_quality(&[0, 1, 2], "abc"), 1.0);
+/// assert_eq!(match_quality(&[0, 1], "abcd"), 0.5);
+/// assert_eq!(match_quality(&[0, 3], "abcd"), 0.25);
+/// assert_eq!(match_quality(&[], "abcd"), 0.0);
+/// ```
+pub fn match_quality(indices: &[usize], value: &str) -> f64 {
+    let (Some(first), Some(last)) = (indices.first(), indices.last()) else {
+        return 0.0;
+    };
+    let matched = indices.len();
+    let span = last.saturating_sub(*first) + 1;
+    let len = value.chars().count().max(matched);
+    ratio(matched, len) * ratio(matched, span.max(matched))
+}
+
+/// Score of every option against an empty filter, `None` if they're hidden until
+/// something is typed.
+fn empty_filter_score(show_all: bool) -> Option<FuzzyScore> {
+    show_all.then(|| FuzzyScore {
+        score: 0,
+        indices: Vec::new(),
+        field: 0,
+        quality: 0.0,
+    })
+}
+
+/// `part / whole` as a float.
+#[allow(clippy::cast_precision_loss)]
+fn ratio(part: usize, whole: usize) -> f64 {
+    part as f64 / whole as f64
+}
+
+/// The filter as matched, trimmed with inner runs of whitespace collapsed if `trim`,
+/// then normalized as `scorer` normalizes options.  Tokens shorter than
+/// `min_token_len` characters are dropped, as if not yet typed.
+fn normalize_filter<'f>(
+    filter: &'f str,
+    trim: bool,
+    min_token_len: usize,
+    scorer: &Scorer,
+) -> Cow<'f, str> {
+    let filter = if min_token_len > 1 {
+        Cow::Owned(
+            filter
+                .split_whitespace()
+                .filter(|token| token.graphemes(true).count() >= min_token_len)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    } else if trim {
+        Cow::Owned(filter.split_whitespace().collect::<Vec<_>>().join(" "))
+    } else {
+        Cow::Borrowed(filter)
+    };
+    if let Cow::Owned(normalized) = scorer.normalize(&filter) {
+        return Cow::Owned(normalized);
+    }
+    filter
+}
+
+/// Removes diacritics from `text`, e.g. "résumé" becomes "resume".
+fn strip_diacritics(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.nfd().filter(|c| !is_combining_mark(*c)).collect())
+}
+
+/// Cleans up options as they're pushed, e.g. lines of logs, so that stray control
+/// chars don't corrupt rendering.  Options are then matched, highlighted and displayed
+/// clean, the original being kept for `FuzzyListEntry::original`.  Off by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sanitizer {
+    /// Keep options as pushed.
+    #[default]
+    None,
+    /// Replace tabs with spaces, and drop other control chars, e.g. `\r` or null
+    /// bytes, and zero-width spaces.
+    ControlChars,
+    /// Drop ANSI escape sequences, e.g. SGR colors, keeping the text they style, then
+    /// clean up control chars as `ControlChars` does.
+    Ansi,
+    /// As `Ansi`, but keep the colors and modifiers of SGR sequences, e.g. from
+    /// `ls --color`, for `FuzzyList` to display the option in, under its own styles.
+    AnsiStyled,
+}
+
+impl Sanitizer {
+    /// Cleans up `text`, borrowing it if there's nothing to clean.
+    fn apply(self, text: &str) -> Cow<'_, str> {
+        if matches!(self, Self::None) || !text.contains(is_dirty) {
+            return Cow::Borrowed(text);
+        }
+        match self {
+            Self::Ansi | Self::AnsiStyled => Cow::Owned(parse_ansi(text).0),
+            _ => Cow::Owned(text.chars().filter_map(clean_char).collect()),
+        }
+    }
+}
+
+/// Whether `c` is cleaned up by `Sanitizer::ControlChars`.
+fn is_dirty(c: char) -> bool {
+    c.is_control() || c == '\u{200b}' || c == '\u{feff}'
+}
+
+/// `c` as cleaned up by `Sanitizer::ControlChars`, `None` if dropped.
+fn clean_char(c: char) -> Option<char> {
+    match c {
+        '\t' => Some(' '),
+        c if is_dirty(c) => None,
+        c => Some(c),
+    }
+}
+
+/// Colors of SGR codes 30 to 37, then 90 to 97.
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Splits ANSI styled `text` into its visible text, cleaned up as
+/// `Sanitizer::ControlChars` does, and the style it's in from each char index on, as
+/// set by SGR sequences.  Other CSI sequences, OSC sequences such as hyperlinks, and
+/// two char escapes are dropped.
+fn parse_ansi(text: &str) -> (String, Vec<(usize, Style)>) {
+    let mut visible = String::with_capacity(text.len());
+    let mut styles: Vec<(usize, Style)> = Vec::new();
+    let mut style = Style::default();
+    let mut len = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if let Some(c) = clean_char(c) {
+                visible.push(c);
+                len += 1;
+            }
+            continue;
+        }
+        match chars.next() {
+            // parameters, then a final byte in `@..=~`, `m` for SGR.
+            Some('[') => {
+                let mut params = String::new();
+                let Some(end) = chars.find(|&c| {
+                    let done = ('@'..='~').contains(&c);
+                    if !done {
+                        params.push(c);
+                    }
+                    done
+                }) else {
+                    break;
+                };
+                if end == 'm' {
+                    style = apply_sgr(style, &params);
+                    if styles.last().map_or(false, |(start, _)| *start == len) {
+                        styles.pop();
+                    }
+                    styles.push((len, style));
+                }
+            }
+            // terminated by BEL or ST, `ESC \`.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    (visible, styles)
+}
+
+/// `style` updated by the `;` separated codes of an SGR sequence.
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|code| code.parse::<u16>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(ANSI_COLORS[usize::from(code - 30)]),
+            40..=47 => style.bg(ANSI_COLORS[usize::from(code - 40)]),
+            90..=97 => style.fg(ANSI_COLORS[usize::from(code - 82)]),
+            100..=107 => style.bg(ANSI_COLORS[usize::from(code - 92)]),
+            39 => Style { fg: None, ..style },
+            49 => Style { bg: None, ..style },
+            38 | 48 => {
+                let mut next = || codes.next().and_then(|code| u8::try_from(code).ok());
+                let color = match next() {
+                    Some(5) => next().map(Color::Indexed),
+                    Some(2) => match (next(), next(), next()) {
+                        (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match color {
+                    Some(color) if code == 38 => style.fg(color),
+                    Some(color) => style.bg(color),
+                    None => style,
+                }
+            }
+            _ => style,
+        };
+    }
+    style
+}
+
+/// Maps the filter and options to a canonical form before they're matched, e.g. so
+/// "resume" finds "résumé".  Highlighting still applies to options as pushed, each
+/// normalized char highlighting the char it came from.  That's exact where chars map
+/// one to one, e.g. case folding or stripping an accent from a precomposed letter,
+/// and best effort otherwise: a char expanding to several, e.g. "æ" to "ae", is
+/// highlighted if any of them matched, and chars normalized away, e.g. the combining
+/// accent of a decomposed "é", are never highlighted.
+#[derive(Clone, Copy, Default)]
+pub enum Normalizer {
+    /// Match text as is.
+    #[default]
+    None,
+    /// Strip diacritics, so accented letters match their plain counterparts.
+    StripDiacritics,
+    /// Normalize with a custom function.  Options are normalized a char at a time, so
+    /// matches can be traced back to the chars they came from, meaning the function
+    /// must work char by char, e.g. folding or transliterating, not across words.
+    Custom(fn(&str) -> Cow<'_, str>),
+}
+
+impl Normalizer {
+    /// Normalizes `text`.
+    fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::None => Cow::Borrowed(text),
+            Self::StripDiacritics => strip_diacritics(text),
+            Self::Custom(normalize) => normalize(text),
+        }
+    }
+
+    /// Normalizes `text` a char at a time, dropping `skipped` chars, alongside the
+    /// index in `text` of the char each normalized char came from.  `None` if there's
+    /// no normalization to do.
+    fn apply_mapped(self, text: &str, skipped: &[char]) -> Option<(String, Vec<usize>)> {
+        if matches!(self, Self::None) && skipped.is_empty() {
+            return None;
+        }
+        let mut normalized = String::with_capacity(text.len());
+        let mut origins = Vec::with_capacity(text.len());
+        let mut buf = [0; 4];
+        for (index, c) in text.chars().enumerate() {
+            if skipped.contains(&c) {
+                continue;
+            }
+            let piece = self.apply(c.encode_utf8(&mut buf));
+            origins.extend(iter::repeat(index).take(piece.chars().count()));
+            normalized.push_str(&piece);
+        }
+        Some((normalized, origins))
+    }
+}
+
+/// How options are matched against the filter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Fuzzy matching, via skim's algorithm.
+    #[default]
+    Fuzzy,
+    /// Case-sensitive substring matching, earlier matches ranking higher.
+    Substring,
+    /// Fuzzy matching, with a large bonus when the filter's chars are the initials
+    /// of words or `camelCase` humps, e.g. "gcd" for "Greatest Common Divisor".
+    Acronym,
+    /// fzf's extended search syntax: every whitespace separated token must match,
+    /// fuzzily unless marked up as a `FilterToken`, e.g. "^src 'test .rs$ !mock".
+    Extended,
+}
+
+impl MatchMode {
+    /// Scores `value` against `filter`, `None` if it doesn't match.
+    fn score(self, matcher: &dyn FuzzyMatcher, value: &str, filter: &str) -> Option<FuzzyScore> {
+        match self {
+            Self::Fuzzy => score(matcher, value, filter),
+            Self::Acronym => {
+                let fuzzy = score(matcher, value, filter);
+                let Some(indices) = acronym_indices(value, filter) else {
+                    return fuzzy;
+                };
+                let bonus = ACRONYM_BONUS * i64::try_from(indices.len()).unwrap_or(i64::MAX);
+                Some(FuzzyScore {
+                    score: fuzzy.map_or(0, |fuzzy| fuzzy.score).saturating_add(bonus),
+                    quality: match_quality(&indices, value),
+                    indices,
+                    field: 0,
+                })
+            }
+            Self::Extended => score_extended(matcher, value, filter),
+            Self::Substring => score_substring(value, filter),
+        }
+    }
+
+    /// Whether `value` is exactly `filter`, as case sensitive as matching with
+    /// `algorithm` is.
+    fn is_exact(self, algorithm: Algorithm, value: &str, filter: &str) -> bool {
+        match self {
+            Self::Fuzzy | Self::Acronym
+                if algorithm.smart_case() && !filter.chars().any(char::is_uppercase) =>
+            {
+                value.to_lowercase() == filter
+            }
+            _ => value == filter,
+        }
+    }
+
+    /// Whether every option matching `previous` could match `filter` too, so only
+    /// those need rescoring.  In `Extended` mode a longer filter can mean a different
+    /// token, e.g. "!x" excludes where "!" matched fuzzily, and a longer exclusion or
+    /// suffix rules out less, so only fuzzy, exact and prefix tokens may grow.
+    fn narrows(self, previous: &str, filter: &str) -> bool {
+        if previous.is_empty() || filter.len() <= previous.len() || !filter.starts_with(previous) {
+            return false;
+        }
+        if self != Self::Extended {
+            return true;
+        }
+        let mut tokens = filter_tokens(filter);
+        filter_tokens(previous).all(|was| match (was, tokens.next()) {
+            (FilterToken::Fuzzy(_), Some(FilterToken::Fuzzy(_)))
+            | (FilterToken::Exact(_), Some(FilterToken::Exact(_)))
+            | (FilterToken::Prefix(_), Some(FilterToken::Prefix(_))) => true,
+            (was, is) => Some(was) == is,
+        })
+    }
+}
+
+/// Scores `value` by where `filter` is found in it, case sensitively, earlier matches
+/// scoring higher.  `None` if it isn't found.
+fn score_substring(value: &str, filter: &str) -> Option<FuzzyScore> {
+    value.find(filter).map(|offset| {
+        let start = value[..offset].chars().count();
+        let indices: Vec<usize> = (start..start + filter.chars().count()).collect();
+        FuzzyScore {
+            score: -i64::try_from(start).unwrap_or(i64::MAX),
+            quality: match_quality(&indices, value),
+            indices,
+            field: 0,
+        }
+    })
+}
+
+/// Matcher for `Algorithm::Exact`, finding the pattern as a substring.
+#[derive(Default)]
+struct ExactMatcher;
+
+impl FuzzyMatcher for ExactMatcher {
+    fn fuzzy_indices(&self, choice: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+        score_substring(choice, pattern).map(|score| (score.score, score.indices))
+    }
+}
+
+/// Matching algorithm used in `MatchMode::Fuzzy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// skim's algorithm, as in fzf: favours contiguous runs and matches at word
+    /// starts.  Smart case.  Fast, and the best general purpose choice.
+    #[default]
+    SkimV2,
+    /// No fuzziness: options match if they contain the filter, case sensitively,
+    /// earlier occurrences ranking higher, as in `MatchMode::Substring`.  Unlike that
+    /// mode, other modes built on fuzzy matching, e.g. `MatchMode::Extended`'s plain
+    /// tokens, match exactly too.
+    Exact,
+    /// nucleo's algorithm, as in helix: fzf-like ranking, several times faster than
+    /// skim's on very large option lists.  Smart case.  Needs the `nucleo` feature.
+    #[cfg(feature = "nucleo")]
+    Nucleo,
+}
+
+impl Algorithm {
+    /// A matcher running this algorithm.
+    fn matcher(self) -> Arc<dyn FuzzyMatcher> {
+        match self {
+            Self::SkimV2 => Arc::<SkimMatcherV2>::default(),
+            Self::Exact => Arc::<ExactMatcher>::default(),
+            #[cfg(feature = "nucleo")]
+            Self::Nucleo => Arc::<NucleoMatcher>::default(),
+        }
+    }
+
+    /// Whether matching ignores case unless the filter contains uppercase.
+    fn smart_case(self) -> bool {
+        self != Self::Exact
+    }
+}
+
+/// Adapts nucleo's matcher to `FuzzyMatcher`, so its results come out as the same
+/// `FuzzyScore`s as every other algorithm.
+#[cfg(feature = "nucleo")]
+#[derive(Default)]
+struct NucleoMatcher;
+
+#[cfg(feature = "nucleo")]
+impl FuzzyMatcher for NucleoMatcher {
+    fn fuzzy_indices(&self, choice: &str, pattern: &str) -> Option<(i64, Vec<usize>)> {
+        use nucleo_matcher::{Config, Matcher, Utf32Str};
+        use std::cell::RefCell;
+
+        // nucleo's matcher holds scratch buffers, so keep one per scoring thread.
+        thread_local! {
+            static MATCHER: RefCell<Matcher> = RefCell::new(Matcher::new(Config::DEFAULT));
+        }
+        let (mut haystack, mut needle, mut indices) = (Vec::new(), Vec::new(), Vec::new());
+        let score = MATCHER.with(|matcher| {
+            let mut matcher = matcher.borrow_mut();
+            matcher.config.ignore_case = !pattern.chars().any(char::is_uppercase);
+            matcher.config.normalize = false;
+            matcher.fuzzy_indices(
+                Utf32Str::new(choice, &mut haystack),
+                Utf32Str::new(pattern, &mut needle),
+                &mut indices,
+            )
+        })?;
+        let mut indices: Vec<usize> = indices
+            .into_iter()
+            .filter_map(|index| usize::try_from(index).ok())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        Some((i64::from(score), indices))
+    }
+
+    fn fuzzy_match(&self, choice: &str, pattern: &str) -> Option<i64> {
+        self.fuzzy_indices(choice, pattern).map(|(score, _)| score)
+    }
+}
+
+/// Which fields of a multi-field option must match the filter for the option to match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldMatch {
+    /// The option matches if any field does, e.g. a search box over heterogeneous
+    /// records.
+    #[default]
+    Any,
+    /// The option matches only if every field does.
+    All,
+}
+
+/// Ordering of matches supplied in place of ranking by score.
+type Comparator = Box<dyn Fn(&FuzzyListEntry, &FuzzyListEntry) -> Ordering + Send + Sync>;
+
+/// Receiver of metrics for each rescore, see `FuzzyFinder::set_metrics_sink`.
+type MetricsSink = Box<dyn Fn(UpdateMetrics) + Send + Sync>;
+
+/// How options are scored against the filter.
+#[derive(Clone, Default)]
+struct Scorer {
+    /// How options are matched against the filter.
+    match_mode: MatchMode,
+    /// Canonical form the filter and options are mapped to before matching.
+    normalizer: Normalizer,
+    /// Boosts added to scores of matches in each field, by field index.
+    field_weights: Vec<i64>,
+    /// Whether options are paths, matched by file name before the whole path.
+    paths: bool,
+    /// Which fields of an option must match for it to.
+    field_match: FieldMatch,
+    /// Fuzzy matching algorithm.
+    algorithm: Algorithm,
+    /// Matcher for `Algorithm::SkimV2` configured by `with_matcher_options`, if any.
+    skim_matcher: Option<Arc<SkimMatcherV2>>,
+    /// Whether nothing matches an empty filter, rather than everything.
+    hide_all_on_empty: bool,
+    /// Whether matches keep the order options were pushed in, rather than by score.
+    preserve_input_order: bool,
+    /// Chars dropped from the filter and options before matching, e.g. path separators.
+    optional_separators: Vec<char>,
+    /// Boost added to scores of matches ending at the field's last char.
+    suffix_bonus: i64,
+}
+
+impl Scorer {
+    /// A matcher to score with.
+    fn matcher(&self) -> Arc<dyn FuzzyMatcher> {
+        match self.skim_matcher {
+            Some(ref matcher) if self.algorithm == Algorithm::SkimV2 => matcher.clone(),
+            _ => self.algorithm.matcher(),
+        }
+    }
+
+    /// Normalizes `text`, dropping optional separators.
+    fn normalize<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.optional_separators.is_empty() {
+            return self.normalizer.apply(text);
+        }
+        let kept: String = text
+            .chars()
+            .filter(|c| !self.optional_separators.contains(c))
+            .collect();
+        Cow::Owned(self.normalizer.apply(&kept).into_owned())
+    }
+
+    /// Scores `value`, once normalized, against an already normalized `filter`, with
+    /// indices into `value` as it was.
+    fn score(&self, matcher: &dyn FuzzyMatcher, value: &str, filter: &str) -> Option<FuzzyScore> {
+        let Some((normalized, origins)) = self
+            .normalizer
+            .apply_mapped(value, &self.optional_separators)
+        else {
+            return self.match_mode.score(matcher, value, filter);
+        };
+        let mut score = self.match_mode.score(matcher, &normalized, filter)?;
+        score.indices = score
+            .indices
+            .iter()
+            .filter_map(|index| origins.get(*index).copied())
+            .collect();
+        // several normalized chars may come from the same one.
+        score.indices.dedup();
+        score.quality = match_quality(&score.indices, value);
+        Some(score)
+    }
+
+    /// Scores `path` by its file name, falling back to the whole path, with indices
+    /// into the whole path.
+    fn score_path(
+        &self,
+        matcher: &dyn FuzzyMatcher,
+        path: &str,
+        filter: &str,
+    ) -> Option<FuzzyScore> {
+        let name_start = path.rfind(is_separator).map_or(0, |offset| offset + 1);
+        if name_start > 0 {
+            if let Some(mut score) = self.score(matcher, &path[name_start..], filter) {
+                let offset = path[..name_start].chars().count();
+                for index in &mut score.indices {
+                    *index += offset;
+                }
+                score.quality = match_quality(&score.indices, path);
+                return Some(score);
+            }
+        }
+        self.score(matcher, path, filter)
+    }
+
+    /// Scores `option`, keyed by `value`, against `filter` across its fields.
+    fn score_option(
+        &self,
+        matcher: &dyn FuzzyMatcher,
+        value: &str,
+        option: &FuzzyOption,
+        filter: &str,
+    ) -> Option<FuzzyScore> {
+        let fields =
+            iter::once(option.searched(value)).chain(option.fields.iter().map(AsRef::as_ref));
+        self.score_fields(matcher, fields, filter)
+    }
+
+    /// Scores each of `fields` against `filter`, boosted by its field weight, keeping
+    /// the best.  Ties go to the earlier field.  `None` if no field matches, or any
+    /// doesn't for `FieldMatch::All`.
+    fn score_fields<'f>(
+        &self,
+        matcher: &dyn FuzzyMatcher,
+        fields: impl Iterator<Item = &'f str>,
+        filter: &str,
+    ) -> Option<FuzzyScore> {
+        let mut best: Option<FuzzyScore> = None;
+        for (field, value) in fields.enumerate() {
+            let score = if self.paths && field == 0 {
+                self.score_path(matcher, value, filter)
+            } else {
+                self.score(matcher, value, filter)
+            };
+            let Some(mut score) = score else {
+                if self.field_match == FieldMatch::All {
+                    return None;
+                }
+                continue;
+            };
+            let weight = self.field_weights.get(field).copied().unwrap_or(0);
+            score.score = score.score.saturating_add(weight);
+            if self.suffix_bonus != 0
+                && score.indices.last().map(|last| last + 1) == Some(value.chars().count())
+            {
+                score.score = score.score.saturating_add(self.suffix_bonus);
+            }
+            score.field = field;
+            // scores order best first.
+            if best.as_ref().map_or(true, |best| score < *best) {
+                best = Some(score);
+            }
+        }
+        best
+    }
+}
+
+/// A searchable option, with its score against the current filter.
+#[derive(Default)]
+pub struct FuzzyOption<'a> {
+    /// fuzzy match score, `None` if the option doesn't match the filter
+    pub score: Option<FuzzyScore>,
+    /// secondary text displayed alongside the option, not matched against
+    pub description: Option<Cow<'a, str>>,
+    /// further fields matched against the filter, displayed after the option
+    pub fields: Vec<Cow<'a, str>>,
+    /// prefix displayed before the option, e.g. a file type icon, not matched against
+    pub icon: Option<Span<'a>>,
+    /// label of the group the option's match is listed under, e.g. a plugin name
+    pub group: Option<Cow<'a, str>>,
+    /// text matched in place of the option, which is still displayed unhighlighted
+    pub search_key: Option<Cow<'a, str>>,
+    /// boost added to the score when ranking, e.g. for frecency
+    pub weight: i64,
+    /// breaks ties between equally scored options, highest first, e.g. CPU usage
+    pub sort_key: i64,
+    /// whether the option ranks above unpinned options whenever it matches
+    pub pinned: bool,
+    /// whether the option was used before, e.g. run this session, for dimming
+    pub used: bool,
+    /// when the option was pushed, breaking ties in ranking
+    insertion: usize,
+    /// whether the option is yet to be scored against the filter when scoring lazily
+    unscored: bool,
+    /// the option sanitized and cut to `FuzzyFinder::set_max_option_len`, if either
+    /// changed it
+    truncated: Option<String>,
+    /// styles of the displayed option from each char index on, parsed by
+    /// `Sanitizer::AnsiStyled`
+    pub(crate) styles: Vec<(usize, Style)>,
+}
+
+impl FuzzyOption<'_> {
+    /// Replaces the option's score, returning whether that changed its ranking or
+    /// highlighting.
+    fn rescore(&mut self, score: Option<FuzzyScore>) -> bool {
+        let changed = match (&self.score, &score) {
+            (Some(old), Some(new)) => {
+                old.score != new.score || old.field != new.field || old.indices != new.indices
+            }
+            (old, new) => old.is_some() != new.is_some(),
+        };
+        self.score = score;
+        self.unscored = false;
+        changed
+    }
+
+    /// Ordering in the ranked matches: matches first, pinned first, then by weighted
+    /// score, best first, ties broken by sort key, highest first, then insertion order.
+    fn cmp_rank(&self, other: &Self) -> Ordering {
+        match (self.weighted_score(), other.weighted_score()) {
+            // pinned first, then highest score first
+            (Some(s1), Some(s2)) => other
+                .pinned
+                .cmp(&self.pinned)
+                .then(s2.cmp(&s1))
+                .then(other.sort_key.cmp(&self.sort_key))
+                .then(self.insertion.cmp(&other.insertion)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            // options yet to be scored lazily last.
+            (None, None) => self.unscored.cmp(&other.unscored),
+        }
+    }
+
+    /// Text matched and displayed for the option keyed by `value`, which is `value`
+    /// itself unless sanitized or truncated.
+    pub(crate) fn display<'s>(&'s self, value: &'s str) -> &'s str {
+        self.truncated.as_deref().unwrap_or(value)
+    }
+
+    /// Text matched against the filter for the option keyed by `value`, its search
+    /// key if it has one.
+    pub(crate) fn searched<'s>(&'s self, value: &'s str) -> &'s str {
+        self.search_key
+            .as_deref()
+            .unwrap_or_else(|| self.display(value))
+    }
+
+    /// Score used for ranking, the match score boosted by the option's weight.
+    fn weighted_score(&self) -> Option<i64> {
+        self.score
+            .as_ref()
+            .map(|score| score.score.saturating_add(self.weight))
+    }
+}
+
+/// Scores computed off-thread by `FuzzyFinder::set_filter_in_background`.
+struct ScoredBatch {
+    /// Generation of the filter these scores were computed against.
+    generation: u64,
+    /// The normalized filter itself.
+    filter: String,
+    /// Scores keyed by option value.
+    scores: Vec<(String, Option<FuzzyScore>)>,
+}
+
+/// Statistics over the scores of options matching the filter, e.g. to widen a preview
+/// when matches are strong.  Scores are match scores, before option weights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScoreStats {
+    /// highest score
+    pub max: i64,
+    /// lowest score
+    pub min: i64,
+    /// number of matching options
+    pub count: usize,
+}
+
+/// What a rescore of the options did and how long it took, reported to the sink set
+/// with `FuzzyFinder::set_metrics_sink`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateMetrics {
+    /// number of options scored against the filter
+    pub scored: usize,
+    /// number of options matching the filter afterwards
+    pub matched: usize,
+    /// time taken to score and sort the options
+    pub duration: Duration,
+}
+
+/// Where the selection goes when matches are re-ranked, e.g. by a new filter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionPolicy {
+    /// Select the best match.
+    #[default]
+    ResetToTop,
+    /// Keep the selected option selected if it still matches, otherwise select the
+    /// best match.
+    KeepByValue,
+    /// Keep the selection at the same proportion of the way down the matches, e.g.
+    /// halfway, however many there now are.
+    Proportional,
+}
+
+/// What an update did to the rendered results, e.g. to skip redrawing when nothing
+/// changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// neither the results nor the selection changed
+    Unchanged,
+    /// the results are as they were, but a different row is selected
+    Selection,
+    /// matches, their order or their highlighting changed
+    Results,
+}
+
+/// Cuts `value` to at most `max` chars, ending in "…", if it's any longer.
+fn truncate_option(value: &str, max: Option<usize>) -> Option<String> {
+    let max = max?;
+    value.char_indices().nth(max)?;
+    let end = value
+        .char_indices()
+        .nth(max.saturating_sub(1))
+        .map_or(0, |(offset, _)| offset);
+    Some(format!("{}…", &value[..end]))
+}
+
+/// Cleans up `value` with `sanitizer` then cuts it to at most `max` chars, `None` if
+/// neither changes it, alongside any styles parsed from it.
+fn display_option(
+    value: &str,
+    sanitizer: Sanitizer,
+    max: Option<usize>,
+) -> (Option<String>, Vec<(usize, Style)>) {
+    let (clean, styles) = match sanitizer {
+        Sanitizer::AnsiStyled if value.contains('\x1b') => {
+            let (clean, styles) = parse_ansi(value);
+            (Cow::Owned(clean), styles)
+        }
+        _ => (sanitizer.apply(value), Vec::new()),
+    };
+    let display = match clean {
+        Cow::Borrowed(value) => truncate_option(value, max),
+        Cow::Owned(clean) => truncate_option(&clean, max).or(Some(clean)),
+    };
+    (display, styles)
+}
+
 /// Return type for `FuzzyFinder::selection`
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct FuzzyListEntry<'a> {
-    /// value of entry
+    /// value of entry, as matched and displayed
     pub value: &'a str, // TODO not a &str?
+    /// the option as pushed, differing from `value` if cut by
+    /// `FuzzyFinder::set_max_option_len` or cleaned up by `FuzzyFinder::set_sanitizer`
+    pub original: &'a str,
     /// fuzzy match score
     pub score: i64,
-    /// fuzzy match indices (positions in `value`)
+    /// match quality in `0.0..=1.0`, see `match_quality`
+    pub quality: f64,
+    /// fuzzy match indices (positions in the matched field)
     pub indices: Vec<usize>,
+    /// which field matched, 0 being `value`
+    pub field: usize,
+    /// secondary text displayed alongside `value`
+    pub description: Option<&'a str>,
+    /// label of the group listed under, see `FuzzyFinder::push_option_in_group`
+    pub group: Option<&'a str>,
+    /// whether the option was marked used, see `FuzzyFinder::set_used`
+    pub used: bool,
+    /// whether `value` is exactly the filter
+    exact: bool,
+    /// position among the matches, 0 being the best
+    rank: usize,
+}
+
+impl FuzzyListEntry<'_> {
+    /// Whether the entry is exactly the filter, e.g. to offer "press Enter to accept".
+    /// Case sensitivity follows the match mode and algorithm.
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    /// The entry's position among the matches, 0 being the best, e.g. to assert on
+    /// ordering in snapshot tests without depending on the matcher's score scale.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default()
+    ///     .with_filter("ab")
+    ///     .with_options(["xaxb", "abx"]);
+    /// let ranks: Vec<_> = ff.ranked().iter().map(|e| (e.value, e.rank())).collect();
+    /// assert_eq!(ranks, vec![("abx", 0), ("xaxb", 1)]);
+    /// ```
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+}
+
+/// Formats the entry for logs as its value, with runs of matched chars bracketed if
+/// the value matched rather than another field, followed by its score.
+///
+/// # Example
+///
+/// ```
+/// use tuiscope::FuzzyFinder;
+///
+/// let ff = FuzzyFinder::default().with_filter("ac").with_options(["abcd"]);
+/// let selection = ff.selection().unwrap();
+/// assert_eq!(
+///     selection.to_string(),
+///     format!("[a]b[c]d ({})", selection.score)
+/// );
+/// ```
+impl fmt::Display for FuzzyListEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field == 0 {
+            let marked = render_marked(self.value, &self.indices, "[", "]");
+            write!(f, "{marked} ({})", self.score)
+        } else {
+            write!(f, "{} ({})", self.value, self.score)
+        }
+    }
 }
 
 /// State for `FuzzyList<K>`.  Hold on to one of these and pass to `render_stateful_widget`
@@ -66,45 +1145,115 @@ pub struct FuzzyListEntry<'a> {
 ///     f.render_stateful_widget(fuzzy_results, chunks[2], state);
 /// }
 /// ```
+///
+/// # Owned options
+///
+/// Options are held as `Cow<str>`, so a `FuzzyFinder<'static>` takes ownership of
+/// `String`s pushed into it.  A streaming producer can feed lines straight in, with no
+/// separately owned backing `Vec` for the finder to borrow from.
+///
+/// ```
+/// use std::{sync::mpsc::channel, thread};
+/// use tuiscope::FuzzyFinder;
+///
+/// let (tx, rx) = channel::<String>();
+/// let producer = thread::spawn(move || {
+///     for i in 0..100 {
+///         tx.send(format!("line {i}")).unwrap();
+///     }
+/// });
+///
+/// let mut ff: FuzzyFinder<'static> = FuzzyFinder::default();
+/// for line in rx {
+///     ff.push_option(line);
+/// }
+/// producer.join().unwrap();
+/// assert_eq!(ff.len(), 100);
+/// ```
 #[derive(Default)]
 pub struct FuzzyFinder<'a> {
     /// The current filter string.
     filter: Cow<'a, str>,
-    /// IndexMap of FuzzyScore.
-    pub matches: IndexMap<Cow<'a, str>, Option<FuzzyScore>>,
-    /// State for the `FuzzyList` widget's selection.
+    /// Options keyed by value, best match first.
+    pub matches: IndexMap<Cow<'a, str>, FuzzyOption<'a>>,
+    /// State for the `FuzzyList` widget's selection.  Select through `FuzzyFinder`'s
+    /// methods, so the selection follows its option if `matches` are reordered.
     pub state: ListState,
+    /// Key of the selected option, to find it again if `matches` were reordered.
+    selected_key: Option<String>,
+    /// Bumped on every new filter so stale background scores can be discarded.
+    generation: Arc<AtomicU64>,
+    /// Channel on which background scoring results are published.
+    background: Option<(Sender<ScoredBatch>, Receiver<ScoredBatch>)>,
+    /// How options are scored against the filter.
+    scorer: Scorer,
+    /// Number of options ever pushed, used to order options by insertion.
+    insertions: usize,
+    /// Bumped whenever options are added, removed or their fields replaced.
+    options_generation: u64,
+    /// Number of options pushed in a group, so sorting skips grouping if there's none.
+    grouped: usize,
+    /// Bumped whenever matches, their order or their highlighting may have changed, so
+    /// updates can report `Change::Results` without comparing every match.
+    results_generation: u64,
+    /// Matches to score ahead of the selection when scoring lazily.
+    lazy_window: Option<usize>,
+    /// Whether the filter is matched as is, rather than trimmed and collapsed.
+    preserve_filter_whitespace: bool,
+    /// Length in chars beyond which options are truncated for matching and display.
+    max_option_len: Option<usize>,
+    /// How options are cleaned up for matching and display.
+    sanitizer: Sanitizer,
+    /// Matched runs per row beyond which `FuzzyList` highlights their envelope instead.
+    max_highlight_spans: Option<usize>,
+    /// Ordering of matches in place of ranking by score.
+    sort_by: Option<Comparator>,
+    /// Where the selection goes when matches are re-ranked.
+    selection_policy: SelectionPolicy,
+    /// Receiver of metrics for each rescore.
+    metrics_sink: Option<MetricsSink>,
+    /// Statistics over matching scores, kept up to date as matches are sorted.
+    score_stats: Option<ScoreStats>,
+    /// Number of batches in progress, which defer rescoring until they end.
+    batch_depth: usize,
+    /// Rescoring deferred by a batch, `Some(true)` if every option needs rescoring.
+    pending: Option<bool>,
+    /// Whether matches are sorted on the calling thread rather than with rayon.
+    sequential_sort: bool,
+    /// Pool options are scored and sorted on, rayon's global pool if `None`.
+    thread_pool: Option<Arc<ThreadPool>>,
+    /// Whether options are still arriving, see `set_loading`.
+    loading: bool,
+    /// Rows shown when last rendered, how far a page moves the selection.
+    page_len: usize,
+    /// Characters a filter token needs before it's matched, see `set_min_token_len`.
+    min_token_len: usize,
+    /// Normalized filter every score was last computed against, empty if unknown.
+    scored_filter: String,
+    /// Graphemes of the filter before the editing cursor, `None` at the end.
+    #[cfg(feature = "editor")]
+    pub(crate) cursor: Option<usize>,
 }
 
 impl<'a> FuzzyFinder<'a> {
-    /// Clears the filter term.
+    /// Creates a `FuzzyFinder` with room for `capacity` options without reallocating.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let mut ff = FuzzyFinder::default();
-    /// ff.set_filter("foo");
-    /// ff.clear_filter();
+    /// let ff = FuzzyFinder::with_capacity(1_000_000);
     /// ```
-    pub fn clear_filter(&mut self) -> &mut Self {
-        self.filter = Cow::default();
-        self.update_matches(true);
-        self
-    }
-
-    /// Resets the selected line from filtered options to the 0th.
-    fn reset_selection(&mut self) -> &mut Self {
-        if self.matches.is_empty() {
-            self.state.select(None);
-        } else {
-            self.state.select(Some(0));
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            matches: IndexMap::with_capacity(capacity),
+            ..Self::default()
         }
-        self
     }
 
-    /// Select the next filtered entry.
+    /// Reserves room for at least `additional` more options, avoiding rehashing while
+    /// bulk loading.
     ///
     /// # Example
     ///
@@ -112,18 +1261,15 @@ impl<'a> FuzzyFinder<'a> {
     /// use tuiscope::FuzzyFinder;
     ///
     /// let mut ff = FuzzyFinder::default();
-    /// ff.select_next();
+    /// ff.reserve(3);
+    /// ff.push_options(["abc", "bcd", "cde"]);
     /// ```
-    pub fn select_next(&mut self) -> &mut Self {
-        if let Some(current) = self.state.selected() {
-            self.select(current + 1);
-        } else {
-            self.reset_selection();
-        }
+    pub fn reserve(&mut self, additional: usize) -> &mut Self {
+        self.matches.reserve(additional);
         self
     }
 
-    /// Select the previous filtered entry.
+    /// Clears the filter term, returning what that changed.
     ///
     /// # Example
     ///
@@ -131,27 +1277,183 @@ impl<'a> FuzzyFinder<'a> {
     /// use tuiscope::FuzzyFinder;
     ///
     /// let mut ff = FuzzyFinder::default();
-    /// ff.select_next();
-    /// ff.select_next();
-    /// ff.select_prev();
+    /// ff.set_filter("foo");
+    /// ff.clear_filter();
     /// ```
-    pub fn select_prev(&mut self) -> &mut Self {
-        if let Some(current) = self.state.selected() {
-            if current > 0 {
-                self.select(current - 1);
-            }
+    pub fn clear_filter(&mut self) -> Change {
+        self.set_filter(Cow::default())
+    }
+
+    /// Resets the selected line from filtered options to the 0th.
+    fn reset_selection(&mut self) -> &mut Self {
+        if self.matches.is_empty() {
+            self.set_selected(None);
         } else {
-            self.reset_selection();
+            self.set_selected(Some(0));
         }
         self
     }
 
-    fn select(&mut self, index: usize) -> &mut Self {
-        let len = self.matches.len();
+    /// Deselects, keeping the filter and options, e.g. so nothing is committed until
+    /// the user types.  Moving the selection or re-ranking matches selects again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+    /// ff.clear_selection();
+    /// assert!(ff.selection().is_none());
+    /// ff.select_next();
+    /// assert_eq!(ff.selection().unwrap().value, "abc");
+    /// ```
+    pub fn clear_selection(&mut self) -> &mut Self {
+        self.set_selected(None);
+        self
+    }
+
+    /// Selects the option at `index`, remembering its key.
+    fn set_selected(&mut self, index: Option<usize>) {
+        self.state.select(index);
+        self.selected_key = index
+            .and_then(|index| self.matches.get_index(index))
+            .map(|(key, _)| key.to_string());
+    }
+
+    /// Index of the selected option, relocated if `matches` were reordered since it
+    /// was selected.
+    pub(crate) fn selected_index(&self) -> Option<usize> {
+        let index = self.state.selected()?;
+        let Some(ref key) = self.selected_key else {
+            return Some(index);
+        };
+        match self.matches.get_index(index) {
+            Some((at, _)) if at == key => Some(index),
+            _ => self.matches.get_index_of(key.as_str()).or(Some(index)),
+        }
+    }
+
+    /// Select the next filtered entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.select_next();
+    /// ```
+    pub fn select_next(&mut self) -> &mut Self {
+        if let Some(current) = self.selected_index() {
+            self.select(current + 1);
+        } else {
+            self.reset_selection();
+        }
+        self
+    }
+
+    /// Select the previous filtered entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.select_next();
+    /// ff.select_next();
+    /// ff.select_prev();
+    /// ```
+    pub fn select_prev(&mut self) -> &mut Self {
+        if let Some(current) = self.selected_index() {
+            if current > 0 {
+                self.select(current - 1);
+            }
+        } else {
+            self.reset_selection();
+        }
+        self
+    }
+
+    /// Select the next filtered entry satisfying `predicate`, e.g. to jump to the
+    /// next entry of some kind.  The selection stays put if no later entry does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["src/", "Cargo.toml", "tests/"]);
+    /// ff.select_next_where(|entry| entry.value.ends_with('/'));
+    /// assert_eq!(ff.selection().unwrap().value, "tests/");
+    /// ```
+    pub fn select_next_where<P>(&mut self, mut predicate: P) -> &mut Self
+    where
+        P: FnMut(&FuzzyListEntry) -> bool,
+    {
+        let start = self.selected_index().map_or(0, |current| current + 1);
+        let found = (start..self.matches.len())
+            .map_while(|index| self.entry(index).map(|entry| (index, entry)))
+            .find(|(_, entry)| predicate(entry))
+            .map(|(index, _)| index);
+        if let Some(index) = found {
+            self.select(index);
+        }
+        self
+    }
+
+    /// Select the option `value`, e.g. to restore the last selection by name.
+    /// Returns whether it was selected, which it isn't if there's no such option, or
+    /// it doesn't match the filter, leaving the selection unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// assert!(ff.select_value("cde"));
+    /// assert_eq!(ff.selection().unwrap().value, "cde");
+    /// ff.set_filter("b");
+    /// assert!(!ff.select_value("cde"));
+    /// ```
+    pub fn select_value(&mut self, value: &str) -> bool {
+        match self.matches.get_full(value) {
+            Some((index, _, option)) if option.score.is_some() => {
+                self.set_selected(Some(index));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Rows shown when last rendered, at least 1.
+    #[cfg(feature = "crossterm")]
+    pub(crate) fn page_len(&self) -> usize {
+        self.page_len.max(1)
+    }
+
+    pub(crate) fn set_page_len(&mut self, len: usize) {
+        self.page_len = len;
+    }
+
+    pub(crate) fn select(&mut self, index: usize) -> &mut Self {
+        if let Some(window) = self.lazy_window {
+            // the unscored come last.
+            let unscored = self
+                .matches
+                .last()
+                .map_or(false, |(_, option)| option.unscored);
+            if unscored && self.batch_depth == 0 && index + window > self.matched_len() {
+                self.expand_lazily(index + window);
+            }
+        }
+        let len = self.matches.len();
         if len < 1 {
             return self.reset_selection();
         }
-        self.state.select(Some(std::cmp::min(index, len - 1)));
+        self.set_selected(Some(std::cmp::min(index, len - 1)));
         self
     }
 
@@ -168,118 +1470,212 @@ impl<'a> FuzzyFinder<'a> {
     /// ff.select_prev();
     /// let answer = ff.selection();
     /// ```
-    pub fn selection(&self) -> Option<FuzzyListEntry> {
-        self.state.selected().and_then(|i| {
-            self.matches.get_index(i).and_then(|(value, score)| {
-                score
-                    .as_ref()
-                    .map(|FuzzyScore { score, indices }| FuzzyListEntry {
-                        value,
-                        indices: indices.clone(),
-                        score: *score,
-                    })
-            })
+    pub fn selection(&self) -> Option<FuzzyListEntry<'_>> {
+        self.selected_index().and_then(|i| self.entry(i))
+    }
+
+    /// The selected entry's index among the matches and its value as pushed, owned so
+    /// the finder can be dropped or changed afterwards, e.g. when the user presses
+    /// Enter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+    /// ff.select_next();
+    /// assert_eq!(ff.commit(), Some((1, "bcd".to_string())));
+    /// ```
+    pub fn commit(&self) -> Option<(usize, String)> {
+        let index = self.selected_index()?;
+        let entry = self.entry(index)?;
+        Some((index, entry.original.to_string()))
+    }
+
+    /// Get the highest ranked entry, regardless of the selection, e.g. for a search
+    /// box where Enter always takes the top hit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// ff.set_filter("cd");
+    /// ff.select_next();
+    /// assert_eq!(ff.best_match().unwrap().value, "cde");
+    /// assert_eq!(ff.selection().unwrap().value, "bcd");
+    /// ```
+    pub fn best_match(&self) -> Option<FuzzyListEntry<'_>> {
+        self.entry(0)
+    }
+
+    /// Entries matching the current filter, in display order.  Useful headless, e.g.
+    /// to print ranked results from a CLI, or to test ranking without rendering.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default()
+    ///     .with_filter("cd")
+    ///     .with_options(["abc", "bcd", "cde"]);
+    /// let ranked: Vec<_> = ff.ranked().into_iter().map(|entry| entry.value).collect();
+    /// assert_eq!(ranked, vec!["cde", "bcd"]);
+    /// ```
+    pub fn ranked(&self) -> Vec<FuzzyListEntry<'_>> {
+        (0..self.matches.len())
+            .map_while(|index| self.entry(index))
+            .collect()
+    }
+
+    /// Every option in display order with its score, `None` if it doesn't match the
+    /// filter.  A read-only view for assertions, without rendering or reaching into
+    /// `matches`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default()
+    ///     .with_filter("tw")
+    ///     .with_options(["one", "two"]);
+    /// let results: Vec<_> = ff.results().collect();
+    /// assert_eq!(results[0].0, "two");
+    /// assert_eq!(results[1], ("one", None));
+    /// ```
+    pub fn results(&self) -> impl Iterator<Item = (&str, Option<i64>)> {
+        self.matches.iter().map(|(value, option)| {
+            let score = option.score.as_ref().map(|score| score.score);
+            (value.as_ref(), score)
         })
     }
 
-    /// Updates the filter term.
+    /// Get the entry at `index` of the ranked matches, if it matches the filter.
+    fn entry(&self, index: usize) -> Option<FuzzyListEntry<'_>> {
+        let (value, option) = self.matches.get_index(index)?;
+        option.score.as_ref().map(
+            |FuzzyScore {
+                 score,
+                 indices,
+                 field,
+                 quality,
+             }| FuzzyListEntry {
+                value: option.display(value),
+                original: value,
+                indices: if *field == 0 {
+                    // only ever index what's displayed, even for a stale score.
+                    let len = option.display(value).chars().count();
+                    indices.iter().copied().filter(|&i| i < len).collect()
+                } else {
+                    indices.clone()
+                },
+                field: *field,
+                score: *score,
+                quality: *quality,
+                description: option.description.as_deref(),
+                group: option.group.as_deref(),
+                used: option.used,
+                exact: self.is_exact_match(option.display(value)),
+                rank: index,
+            },
+        )
+    }
+
+    /// Number of options, whether or not they match the current filter.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let mut ff = FuzzyFinder::default();
-    /// ff.set_filter("foo");
+    /// let ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// assert_eq!(ff.len(), 3);
     /// ```
-    pub fn set_filter<T: Into<Cow<'a, str>>>(&mut self, filter: T) -> &mut Self {
-        self.filter = filter.into();
-        self.update_matches(true);
-        self
+    pub fn len(&self) -> usize {
+        self.matches.len()
     }
 
-    /// Updates the set of options to search by adding from an iterator.
+    /// A counter bumped whenever options are added, removed or their fields replaced,
+    /// but not by filtering or re-ranking, so caches derived from the options know to
+    /// invalidate without diffing them.  Edits made directly through `matches` aren't
+    /// counted.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let mut ff = FuzzyFinder::default();
-    /// ff.push_options(["abc", "bcd", "cde"]);
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+    /// let generation = ff.options_generation();
+    /// ff.set_filter("b");
+    /// assert_eq!(ff.options_generation(), generation);
+    /// ff.remove_option("abc");
+    /// assert!(ff.options_generation() > generation);
     /// ```
-    pub fn push_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
-        &mut self,
-        options: T,
-    ) -> &mut Self {
-        for option in options {
-            self._push_option(option);
-        }
-        self.update_matches(false);
-        self
+    pub fn options_generation(&self) -> u64 {
+        self.options_generation
     }
 
-    /// Builder method which sets search options.
+    /// Whether there are no options at all.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let ff = FuzzyFinder::default().with_options(["one", "two", "three"]);
+    /// let ff = FuzzyFinder::default();
+    /// assert!(ff.is_empty());
     /// ```
-    pub fn with_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
-        mut self,
-        options: T,
-    ) -> Self {
-        self.set_options(options);
-        self
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
     }
 
-    /// Sets search options.
+    /// Whether there's an option `option`, matching the filter or not.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let mut ff = FuzzyFinder::default();
-    /// ff.set_options(["one", "two", "three"]);
+    /// let ff = FuzzyFinder::default().with_filter("z").with_options(["abc"]);
+    /// assert!(ff.contains("abc"));
+    /// assert!(!ff.contains("xyz"));
     /// ```
-    pub fn set_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
-        &mut self,
-        options: T,
-    ) -> &mut Self {
-        // TODO be more efficient, keep any existing scores for overlapping keys.
-        // Maybe leverage `remove_options` when an efficient  version of that has
-        // been made.
-        self.matches.clear();
-        self.push_options(options);
-        self
+    pub fn contains<R: AsRef<str>>(&self, option: R) -> bool {
+        self.matches.contains_key(option.as_ref())
     }
 
-    /// Add an option to search.
+    /// The current match score of `option`, `None` if it doesn't match the filter or
+    /// there's no such option.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let mut ff = FuzzyFinder::default();
-    /// ff.push_option("hello");
+    /// let ff = FuzzyFinder::default().with_filter("b").with_options(["abc", "xyz"]);
+    /// assert!(ff.score_of("abc").is_some());
+    /// assert_eq!(ff.score_of("xyz"), None);
     /// ```
-    pub fn push_option<R: Into<Cow<'a, str>>>(&mut self, option: R) {
-        self._push_option(option);
-        self.update_matches(false);
+    pub fn score_of<R: AsRef<str>>(&self, option: R) -> Option<i64> {
+        self.matches
+            .get(option.as_ref())?
+            .score
+            .as_ref()
+            .map(|score| score.score)
     }
 
-    /// Adds an option to search without updating.
-    fn _push_option<R: Into<Cow<'a, str>>>(&mut self, option: R) {
-        // keep existing score if entry exists.
-        self.matches.entry(option.into()).or_insert(None);
+    /// Whether options are still being loaded, as set by `set_loading`.
+    pub fn is_loading(&self) -> bool {
+        self.loading
     }
 
-    /// Removes an option.
+    /// Marks the options as still arriving, e.g. while streaming them from a pipe, so
+    /// a `FuzzyList` can show its loading indicator.
     ///
     /// # Example
     ///
@@ -287,76 +1683,3426 @@ impl<'a> FuzzyFinder<'a> {
     /// use tuiscope::FuzzyFinder;
     ///
     /// let mut ff = FuzzyFinder::default();
-    /// ff.push_options(["hello", "friend"]);
-    /// ff.remove_option("hello");
+    /// ff.set_loading(true);
+    /// ff.push_options(["abc", "bcd"]);
+    /// ff.set_loading(false);
+    /// assert!(!ff.is_loading());
     /// ```
-    pub fn remove_option<R: AsRef<str>>(&mut self, key: R) {
-        self.matches.shift_remove(key.as_ref());
+    pub fn set_loading(&mut self, loading: bool) -> &mut Self {
+        self.loading = loading;
+        self
     }
 
-    /// Removes multiple options.
+    /// Whether any option matches the current filter.
     ///
     /// # Example
     ///
     /// ```
     /// use tuiscope::FuzzyFinder;
     ///
-    /// let mut ff = FuzzyFinder::default();
-    /// ff.push_options(["hello", "my", "old", "friend"]);
-    /// ff.remove_options(["my", "old"]);
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// ff.set_filter("zzz");
+    /// assert!(!ff.has_matches());
     /// ```
-    pub fn remove_options<T: 'a + IntoIterator<Item = R>, R: AsRef<str>>(&mut self, keys: T) {
-        // TODO something smarter, this will O(n) shift all entries in `self.matches`
-        // for each key.  Will in certain cases be better to just `self.matches.remove`
-        // followed by a sort.
-        for key in keys {
-            self.remove_option(key);
+    pub fn has_matches(&self) -> bool {
+        // matches are sorted, so any `Some` score comes first.
+        matches!(
+            self.matches.first(),
+            Some((_, FuzzyOption { score: Some(_), .. }))
+        )
+    }
+
+    /// Range of match indices displayed in a list `height` rows tall, scrolled as
+    /// `FuzzyList` would to keep the selection in view, counting group headers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["a", "b", "c", "d", "e"]);
+    /// assert_eq!(ff.visible_range(3), 0..3);
+    /// ff.select_next().select_next().select_next();
+    /// assert_eq!(ff.visible_range(3), 1..4);
+    /// ```
+    pub fn visible_range(&self, height: usize) -> Range<usize> {
+        self.scrolled_range(height, false)
+    }
+
+    /// `visible_range` for a list which is `reversed` or not, a reversed `FuzzyList`
+    /// listing each group header after its group's last match.
+    pub(crate) fn scrolled_range(&self, height: usize, reversed: bool) -> Range<usize> {
+        let len = self.matched_len();
+        if len == 0 || height == 0 {
+            return 0..0;
+        }
+        let mut start = self.state.offset().min(len - 1);
+        let selected = self.selected_index().unwrap_or(0).min(len - 1);
+        if selected >= start + height {
+            start = selected + 1 - height;
+        } else if selected < start {
+            start = selected;
+        }
+        // group headers take rows too, so scroll further until the selection fits.
+        let rows = |index| 1 + usize::from(self.has_header(index, reversed));
+        let mut used: usize = (start..=selected).map(rows).sum();
+        while used > height && start < selected {
+            used -= rows(start);
+            start += 1;
+        }
+        let mut end = start;
+        let mut used = 0;
+        while end < len {
+            let rows = rows(end);
+            if end > start && used + rows > height {
+                break;
+            }
+            used += rows;
+            end += 1;
         }
+        start..end
     }
 
-    /// Computes new scores for all options if `new_filter_term` is true.
-    /// Otherwise competes scores for all options who haven't had a calculation
-    /// yet against the current filter.
-    fn update_matches(&mut self, new_filter_term: bool) {
-        let matcher = SkimMatcherV2::default();
+    /// Whether a group header is listed before match `index`, it being the first of
+    /// its group.
+    fn starts_group(&self, index: usize) -> bool {
+        self.group_of(index).is_some()
+            && (index == 0 || self.group_of(index - 1) != self.group_of(index))
+    }
 
-        // TODO None matches were inserted last, so we should be able to iterate
-        // from the end and stop early.  But I couldn't quite find the right
-        // early-stopping option for an IndexedParallesIterator
-        // iter = iter.rev().take_any_while... race behavior is not ideal
+    /// Whether match `index` is the last of its group, which a reversed `FuzzyList`
+    /// lists the group header after.
+    fn ends_group(&self, index: usize) -> bool {
+        self.group_of(index).is_some() && self.group_of(index + 1) != self.group_of(index)
+    }
+
+    /// Whether a `FuzzyList`, `reversed` or not, lists a group header with match
+    /// `index`.
+    pub(crate) fn has_header(&self, index: usize, reversed: bool) -> bool {
+        if reversed {
+            self.ends_group(index)
+        } else {
+            self.starts_group(index)
+        }
+    }
+
+    /// Group of match `index`, `None` if it has none or isn't a match.
+    fn group_of(&self, index: usize) -> Option<&str> {
         self.matches
-            .par_iter_mut()
-            .filter(|(_, score)| new_filter_term || score.is_none())
-            .for_each(|(value, score)| {
-                *score = matcher
-                    .fuzzy_indices(value, &self.filter)
-                    .map(|(score, indices)| FuzzyScore { score, indices });
+            .get_index(index)
+            .filter(|(_, option)| option.score.is_some())
+            .and_then(|(_, option)| option.group.as_deref())
+    }
+
+    /// Statistics over the scores of options matching the current filter, `None` if
+    /// nothing matches.  Maintained as matches are ranked, so cheap to call per frame.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// ff.set_filter("cd");
+    /// let stats = ff.score_stats().unwrap();
+    /// assert_eq!(stats.count, 2);
+    /// assert!(stats.max >= stats.min);
+    /// ff.set_filter("zzz");
+    /// assert!(ff.score_stats().is_none());
+    /// ```
+    pub fn score_stats(&self) -> Option<ScoreStats> {
+        self.score_stats
+    }
+
+    /// Recomputes `score_stats` in a pass over the matching options.
+    fn update_score_stats(&mut self) {
+        // matches are sorted, so `Some` scores come first.
+        self.score_stats = self
+            .matches
+            .values()
+            .map_while(|option| option.score.as_ref().map(|score| score.score))
+            .fold(None, |stats, score| {
+                Some(match stats {
+                    None => ScoreStats {
+                        max: score,
+                        min: score,
+                        count: 1,
+                    },
+                    Some(ScoreStats { max, min, count }) => ScoreStats {
+                        max: max.max(score),
+                        min: min.min(score),
+                        count: count + 1,
+                    },
+                })
             });
+    }
 
-        self.matches.par_sort_unstable_by(|_, v1, _, v2| match v1 {
-            Some(v1) => match v2 {
-                Some(v2) => v1.cmp(v2),
-                None => Ordering::Less,
-            },
-            None => Ordering::Greater,
-        });
+    /// Number of options matching the current filter.
+    pub(crate) fn matched_len(&self) -> usize {
+        // matches are sorted, so `Some` scores come first.
+        self.matches
+            .values()
+            .take_while(|option| option.score.is_some())
+            .count()
+    }
 
-        // TODO only if some change
-        self.reset_selection();
+    /// The current filter's tokens in `MatchMode::Extended`'s syntax, e.g. to show
+    /// what each part of the filter does in a query help UI.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FilterToken, FuzzyFinder};
+    ///
+    /// let ff = FuzzyFinder::default().with_filter("^src 'test .rs$ !mock fz");
+    /// assert_eq!(
+    ///     ff.filter_tokens(),
+    ///     vec![
+    ///         FilterToken::Prefix("src"),
+    ///         FilterToken::Exact("test"),
+    ///         FilterToken::Suffix(".rs"),
+    ///         FilterToken::Exclude("mock"),
+    ///         FilterToken::Fuzzy("fz"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn filter_tokens(&self) -> Vec<FilterToken<'_>> {
+        filter_tokens(&self.filter).collect()
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// The current filter term.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default().with_filter("foo");
+    /// assert_eq!(ff.filter(), "foo");
+    /// ```
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
 
-    #[test]
-    fn remove_option() {
-        let mut ff = FuzzyFinder::default();
-        ff.push_options(["hello", "friend"]);
-        assert!(ff.matches.contains_key("hello"));
-        ff.remove_option("hello");
-        assert!(!ff.matches.contains_key("hello"));
+    /// Whether `value` is exactly the current filter.  Case sensitivity follows the
+    /// match mode, e.g. smart case for `MatchMode::Fuzzy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_filter("foo");
+    /// assert!(ff.is_exact_match("Foo"));
+    /// assert!(!ff.is_exact_match("food"));
+    /// ```
+    pub fn is_exact_match(&self, value: &str) -> bool {
+        let value = self.scorer.normalize(value);
+        self.scorer
+            .match_mode
+            .is_exact(self.scorer.algorithm, &value, &self.normalized_filter())
+    }
+
+    /// Updates the filter term, returning what that changed, so a render loop can skip
+    /// redrawing when nothing did.  Setting the same filter again is a no-op, so
+    /// scores and selection are left untouched.  There's no need to clear the filter
+    /// first, which would rescan options twice; to combine several updates into a
+    /// single rescan, see `batch`.  A filter extending the previous one, e.g. as the
+    /// user types, only rescans the options that matched it.
+    ///
+    /// An empty filter matches every option with a score of 0, so options are then
+    /// ordered by weight and, among equal weights, by insertion order.  Unless
+    /// `set_trim_filter(false)`, a filter of only whitespace counts as empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{Change, FuzzyFinder};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["foo", "bar"]);
+    /// assert_eq!(ff.set_filter("fo"), Change::Results);
+    /// assert_eq!(ff.set_filter("foo"), Change::Results);
+    /// assert_eq!(ff.set_filter("foo "), Change::Unchanged);
+    /// ```
+    pub fn set_filter<T: Into<Cow<'a, str>>>(&mut self, filter: T) -> Change {
+        let filter = filter.into();
+        if filter == self.filter {
+            return Change::Unchanged;
+        }
+        self.tracking_changes(|ff| {
+            ff.filter = filter;
+            // an option not matching the filter can't match a longer one.
+            let narrowed = ff
+                .scorer
+                .match_mode
+                .narrows(&ff.scored_filter, &ff.normalized_filter());
+            if narrowed {
+                ff.narrow_matches();
+            } else {
+                ff.update_matches(true);
+            }
+        })
+    }
+
+    /// Appends `text` to the filter term, e.g. as the user types.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.append_to_filter("foo");
+    /// ff.append_to_filter(" b");
+    /// assert_eq!(ff.filter(), "foo b");
+    /// ```
+    pub fn append_to_filter(&mut self, text: &str) -> Change {
+        let filter = format!("{}{text}", self.filter);
+        self.set_filter(filter)
+    }
+
+    /// Removes the last character (grapheme) from the filter term, like Backspace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("foo");
+    /// ff.pop_filter_char();
+    /// assert_eq!(ff.filter(), "fo");
+    /// ```
+    pub fn pop_filter_char(&mut self) -> Change {
+        let end = self
+            .filter
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i);
+        let filter = self.filter[..end].to_string();
+        self.set_filter(filter)
+    }
+
+    /// Removes the last whitespace separated word from the filter term, like Ctrl-W.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("foo bar ");
+    /// ff.pop_filter_word();
+    /// assert_eq!(ff.filter(), "foo ");
+    /// ```
+    pub fn pop_filter_word(&mut self) -> Change {
+        let trimmed = self.filter.trim_end();
+        let end = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let filter = trimmed[..end].to_string();
+        self.set_filter(filter)
+    }
+
+    /// Updates the filter term, scoring options on a background rayon task rather
+    /// than blocking the caller.  Results are applied by `poll_results`, which the
+    /// UI loop should call each tick.  Scoring for a filter which has since been
+    /// superseded is abandoned and its results discarded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// ff.set_filter_in_background("bc");
+    /// while !ff.poll_results() {
+    ///     std::thread::yield_now();
+    /// }
+    /// ```
+    pub fn set_filter_in_background<T: Into<Cow<'a, str>>>(&mut self, filter: T) -> &mut Self {
+        let filter = filter.into();
+        if filter == self.filter {
+            return self;
+        }
+        self.filter = filter;
+        // scores are in flux until the batch arrives, so can't be narrowed.
+        self.scored_filter.clear();
+        let filter = self.normalized_filter().into_owned();
+        if filter.is_empty() {
+            // trivially scored without the matcher, so not worth a background task.
+            self.update_matches(true);
+            return self;
+        }
+        let generation = self.next_generation();
+        let latest = Arc::clone(&self.generation);
+        let scoring = self.scorer.clone();
+        let options: Vec<(String, Vec<String>)> = self
+            .matches
+            .iter()
+            .map(|(value, option)| {
+                let fields = iter::once(option.searched(value))
+                    .chain(option.fields.iter().map(AsRef::as_ref))
+                    .map(ToString::to_string)
+                    .collect();
+                (value.to_string(), fields)
+            })
+            .collect();
+        let (tx, _) = self.background.get_or_insert_with(channel);
+        let tx = tx.clone();
+        let task = move || {
+            let matcher = scoring.matcher();
+            let is_current = || latest.load(atomic::Ordering::Relaxed) == generation;
+            let scores: Vec<_> = options
+                .into_par_iter()
+                .map(|(value, fields)| {
+                    is_current().then(|| {
+                        let fields = fields.iter().map(String::as_str);
+                        let score = scoring.score_fields(matcher.as_ref(), fields, &filter);
+                        (value, score)
+                    })
+                })
+                .while_some()
+                .collect();
+            if is_current() {
+                // the receiver is gone if the finder has been dropped, nothing to do.
+                tx.send(ScoredBatch {
+                    generation,
+                    filter,
+                    scores,
+                })
+                .ok();
+            }
+        };
+        match self.thread_pool {
+            Some(ref pool) => pool.spawn(task),
+            None => rayon::spawn(task),
+        }
+        self
+    }
+
+    /// Applies scores from `set_filter_in_background` if they have arrived.
+    /// Returns `true` if the matches were updated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// assert!(!ff.poll_results());
+    /// ```
+    pub fn poll_results(&mut self) -> bool {
+        let Some((_, rx)) = &self.background else {
+            return false;
+        };
+        let generation = self.generation.load(atomic::Ordering::Relaxed);
+        let Some(batch) = rx
+            .try_iter()
+            .filter(|batch| batch.generation == generation)
+            .last()
+        else {
+            return false;
+        };
+        for (value, score) in batch.scores {
+            // options may have been removed since scoring started.
+            if let Some(option) = self.matches.get_mut(value.as_str()) {
+                if option.rescore(score) {
+                    self.results_generation += 1;
+                }
+            }
+        }
+        self.scored_filter = batch.filter;
+        self.sort_matches();
+        true
+    }
+
+    /// Supersedes any in-flight background scoring, returning the new generation.
+    fn next_generation(&self) -> u64 {
+        self.generation.fetch_add(1, atomic::Ordering::Relaxed) + 1
+    }
+
+    /// Sets whether the filter is trimmed, and inner runs of whitespace collapsed to a
+    /// single space, before matching.  On by default.  `filter` still returns the
+    /// filter as set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["a b"]);
+    /// ff.set_filter("a  b");
+    /// assert!(ff.has_matches());
+    /// ff.set_trim_filter(false);
+    /// assert!(!ff.has_matches());
+    /// ```
+    pub fn set_trim_filter(&mut self, trim: bool) -> &mut Self {
+        if trim == self.preserve_filter_whitespace {
+            self.preserve_filter_whitespace = !trim;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Sets whether every option matches an empty filter, as by default, or none do,
+    /// e.g. for a search box that shouldn't list everything before anything's typed.
+    /// When hidden, nothing is rendered and `selection` is `None` until the filter is
+    /// set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+    /// ff.set_show_all_on_empty(false);
+    /// assert!(ff.selection().is_none());
+    /// ff.set_filter("b");
+    /// assert!(ff.selection().is_some());
+    /// ```
+    pub fn set_show_all_on_empty(&mut self, show_all: bool) -> &mut Self {
+        if show_all == self.scorer.hide_all_on_empty {
+            self.scorer.hide_all_on_empty = !show_all;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Ignores filter tokens, separated by whitespace, shorter than `chars` characters,
+    /// as if they weren't typed yet, since a lone character matches almost everything.
+    /// If every token is too short, everything matches as for an empty filter.  A
+    /// threshold above 1 also trims the filter, as `set_trim_filter(true)` does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "xyz"]);
+    /// ff.set_min_token_len(2);
+    /// ff.set_filter("a");
+    /// assert_eq!(ff.ranked().len(), 2);
+    /// ff.set_filter("ab x");
+    /// assert_eq!(ff.ranked().len(), 1);
+    /// ```
+    pub fn set_min_token_len(&mut self, chars: usize) -> &mut Self {
+        if chars != self.min_token_len {
+            self.min_token_len = chars;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Truncates options longer than `chars`, e.g. multi-kilobyte log lines, to `chars`
+    /// including a trailing "…".  Options are matched, highlighted and displayed
+    /// truncated, which speeds up scoring them, but as the original is kept for
+    /// `FuzzyListEntry::original`, this costs memory for the truncated copies rather
+    /// than saving any.  Options already pushed are truncated and rescored too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_max_option_len(6);
+    /// ff.push_option("a very long line");
+    /// let selection = ff.selection().unwrap();
+    /// assert_eq!(selection.value, "a ver…");
+    /// assert_eq!(selection.original, "a very long line");
+    /// ```
+    pub fn set_max_option_len(&mut self, chars: usize) -> &mut Self {
+        self.max_option_len = Some(chars);
+        self.redisplay_options();
+        self
+    }
+
+    /// Sets how options are cleaned up, e.g. of tabs and ANSI colors in lines piped
+    /// from other tools, for matching and display.  Options already pushed are cleaned
+    /// up and rescored too.  Options are kept as pushed by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FuzzyFinder, Sanitizer};
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_sanitizer(Sanitizer::Ansi);
+    /// ff.push_option("\x1b[1;31merror\x1b[0m:\tdisk full\r");
+    /// let selection = ff.selection().unwrap();
+    /// assert_eq!(selection.value, "error: disk full");
+    /// assert_eq!(selection.original, "\x1b[1;31merror\x1b[0m:\tdisk full\r");
+    /// ```
+    pub fn set_sanitizer(&mut self, sanitizer: Sanitizer) -> &mut Self {
+        if sanitizer != self.sanitizer {
+            self.sanitizer = sanitizer;
+            self.redisplay_options();
+        }
+        self
+    }
+
+    /// Re-derives what's matched and displayed for every option, after the
+    /// sanitizer or maximum length changed, and rescores them.
+    fn redisplay_options(&mut self) {
+        if let Some(pool) = self.pool_to_enter() {
+            return pool.install(|| self.redisplay_options());
+        }
+        let (sanitizer, max) = (self.sanitizer, self.max_option_len);
+        self.matches.par_iter_mut().for_each(|(value, option)| {
+            (option.truncated, option.styles) = display_option(value, sanitizer, max);
+        });
+        self.update_matches(true);
+    }
+
+    /// Caps the matched runs `FuzzyList` highlights separately in a row, capping
+    /// render cost for filters matching many scattered chars of long options.  Beyond
+    /// `spans` runs, the whole span from the first matched char to the last is
+    /// highlighted instead.  Entries' `indices` are unaffected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_max_highlight_spans(8);
+    /// ```
+    pub fn set_max_highlight_spans(&mut self, spans: usize) -> &mut Self {
+        self.max_highlight_spans = Some(spans);
+        self
+    }
+
+    /// Matched runs per row beyond which their envelope is highlighted instead, see
+    /// `set_max_highlight_spans`.
+    pub(crate) fn max_highlight_spans(&self) -> Option<usize> {
+        self.max_highlight_spans
+    }
+
+    /// The filter as matched against options.
+    fn normalized_filter(&self) -> Cow<'_, str> {
+        normalize_filter(
+            &self.filter,
+            !self.preserve_filter_whitespace,
+            self.min_token_len,
+            &self.scorer,
+        )
+    }
+
+    /// Sets how the filter and options are normalized before matching, rescoring.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FuzzyFinder, Normalizer};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["résumé"]);
+    /// ff.set_filter("resume");
+    /// assert!(!ff.has_matches());
+    /// ff.set_normalizer(Normalizer::StripDiacritics);
+    /// assert!(ff.has_matches());
+    /// ```
+    pub fn set_normalizer(&mut self, normalizer: Normalizer) -> &mut Self {
+        self.scorer.normalizer = normalizer;
+        self.update_matches(true);
+        self
+    }
+
+    /// Sets whether matches are sorted in parallel, as by default, or on the calling
+    /// thread.  Scoring stays parallel either way.  A sequential sort is slower on
+    /// large lists, but avoids the extra memory of rayon's parallel merge sort, which
+    /// can matter on constrained targets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_sort_parallel(false);
+    /// ```
+    pub fn set_sort_parallel(&mut self, parallel: bool) -> &mut Self {
+        self.sequential_sort = !parallel;
+        self
+    }
+
+    /// Sets a rayon pool to score and sort options on, in place of rayon's global
+    /// pool, e.g. to bound the threads each of several finders may use.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use rayon::ThreadPoolBuilder;
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let pool = Arc::new(ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_thread_pool(pool);
+    /// ff.push_options(["abc", "bcd"]);
+    /// assert_eq!(ff.ranked().len(), 2);
+    /// ```
+    pub fn set_thread_pool(&mut self, pool: Arc<ThreadPool>) -> &mut Self {
+        self.thread_pool = Some(pool);
+        self
+    }
+
+    /// The pool set by `set_thread_pool`, unless already running on it.
+    fn pool_to_enter(&self) -> Option<Arc<ThreadPool>> {
+        self.thread_pool
+            .as_ref()
+            .filter(|pool| pool.current_thread_index().is_none())
+            .cloned()
+    }
+
+    /// Sets whether matches keep the order options were pushed in, e.g. when already
+    /// ranked by an external system, rather than being sorted by score.  Options
+    /// which don't match are still filtered out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["xaxbx", "ab"]);
+    /// ff.set_preserve_input_order(true);
+    /// ff.set_filter("ab");
+    /// assert_eq!(ff.selection().unwrap().value, "xaxbx");
+    /// ```
+    pub fn set_preserve_input_order(&mut self, preserve: bool) -> &mut Self {
+        if preserve != self.scorer.preserve_input_order {
+            self.scorer.preserve_input_order = preserve;
+            self.resort_matches();
+        }
+        self
+    }
+
+    /// Sets `sink` to receive metrics after each rescore of the options, e.g. to log
+    /// how long scoring and sorting take on large lists.  Scores applied from the
+    /// background by `poll_results` aren't reported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let reported = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&reported);
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// ff.set_metrics_sink(move |metrics| sink.lock().unwrap().push(metrics));
+    /// ff.set_filter("bc");
+    /// let metrics = reported.lock().unwrap()[0];
+    /// assert_eq!((metrics.scored, metrics.matched), (3, 2));
+    /// ```
+    pub fn set_metrics_sink<F: Fn(UpdateMetrics) + Send + Sync + 'static>(
+        &mut self,
+        sink: F,
+    ) -> &mut Self {
+        self.metrics_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Reports metrics for a rescore of `scored` options started at `start` to the
+    /// metrics sink, if any.
+    fn report_metrics(&self, start: Instant, scored: usize) {
+        if let Some(ref sink) = self.metrics_sink {
+            sink(UpdateMetrics {
+                scored,
+                matched: self.score_stats.as_ref().map_or(0, |stats| stats.count),
+                duration: start.elapsed(),
+            });
+        }
+    }
+
+    /// Sets where the selection goes when matches are re-ranked, e.g. by a new filter.
+    /// By default, the best match is selected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FuzzyFinder, SelectionPolicy};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "abd", "xyz"]);
+    /// ff.set_selection_policy(SelectionPolicy::KeepByValue);
+    /// ff.select_next();
+    /// ff.set_filter("ab");
+    /// assert_eq!(ff.selection().unwrap().value, "abd");
+    /// ```
+    pub fn set_selection_policy(&mut self, policy: SelectionPolicy) -> &mut Self {
+        self.selection_policy = policy;
+        self
+    }
+
+    /// Orders matches by `comparator` in place of ranking them by score, e.g. by a
+    /// field of the option or a payload looked up by value.  Matches it considers
+    /// equal keep their ranking by score.  Options which don't match are still
+    /// filtered out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["bb", "a", "ccc"]);
+    /// ff.set_sort_by(Box::new(|e1, e2| e1.value.len().cmp(&e2.value.len())));
+    /// assert_eq!(ff.selection().unwrap().value, "a");
+    /// ```
+    pub fn set_sort_by(&mut self, comparator: Comparator) -> &mut Self {
+        self.sort_by = Some(comparator);
+        self.resort_matches();
+        self
+    }
+
+    /// Sets how options are matched against the filter, rescoring if it changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FuzzyFinder, MatchMode};
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_match_mode(MatchMode::Substring);
+    /// ```
+    pub fn set_match_mode(&mut self, mode: MatchMode) -> &mut Self {
+        if mode != self.scorer.match_mode {
+            self.scorer.match_mode = mode;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Updates the set of options to search by adding from an iterator, returning
+    /// what that changed.  Options are keyed by value, so pushing one equal to an
+    /// existing option leaves a single entry, see `try_set_options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{Change, FuzzyFinder};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("a");
+    /// assert_eq!(ff.push_options(["abc", "bcd", "cde"]), Change::Results);
+    /// assert_eq!(ff.push_options(["xyz"]), Change::Unchanged);
+    /// ```
+    pub fn push_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
+        &mut self,
+        options: T,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            for option in options {
+                ff.upsert_option(option);
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Add an option to search, with a description displayed alongside it, returning
+    /// what that changed.  Only the option itself is matched against the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_with_description("open", "Open a file");
+    /// ```
+    pub fn push_option_with_description<R: Into<Cow<'a, str>>, D: Into<Cow<'a, str>>>(
+        &mut self,
+        option: R,
+        description: D,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            ff.upsert_option(option).description = Some(description.into());
+            ff.update_matches(false);
+        })
+    }
+
+    /// Add an option to search, with an icon displayed before it, returning what that
+    /// changed.  Icons are styled independently of the option and aren't matched
+    /// against the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_with_icon("main.rs", Span::styled("🦀 ", Style::default().fg(Color::Red)));
+    /// ```
+    pub fn push_option_with_icon<R: Into<Cow<'a, str>>, I: Into<Span<'a>>>(
+        &mut self,
+        option: R,
+        icon: I,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            ff.upsert_option(option).icon = Some(icon.into());
+            ff.update_matches(false);
+        })
+    }
+
+    /// Add an option to search, listed under a `group` header, e.g. "Git" in a command
+    /// palette, returning what that changed.  Matches are kept together by group,
+    /// groups ranked by their best match.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_in_group("open file", "File");
+    /// ff.push_option_in_group("commit", "Git");
+    /// ff.push_option_in_group("save file", "File");
+    /// ff.set_filter("com");
+    /// assert_eq!(ff.selection().unwrap().group, Some("Git"));
+    /// ```
+    pub fn push_option_in_group<R: Into<Cow<'a, str>>, G: Into<Cow<'a, str>>>(
+        &mut self,
+        option: R,
+        group: G,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            let option = ff.upsert_option(option);
+            let group = Some(group.into());
+            let regrouped = option.group != group && option.score.is_some();
+            if std::mem::replace(&mut option.group, group).is_none() {
+                ff.grouped += 1;
+            }
+            if regrouped {
+                ff.results_generation += 1;
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Add an option to search, matched by `search_key` in place of the displayed
+    /// option, e.g. a normalized "ada lovelace ada x com" for "Ada Lovelace
+    /// <ada@x.com>", returning what that changed.  Match indices then refer to the
+    /// key, so the option is displayed without highlighting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_with_search_key("Ada Lovelace <ada@x.com>", "ada lovelace ada x com");
+    /// ff.set_filter("ada x");
+    /// assert_eq!(ff.selection().unwrap().value, "Ada Lovelace <ada@x.com>");
+    /// ```
+    pub fn push_option_with_search_key<R: Into<Cow<'a, str>>, K: Into<Cow<'a, str>>>(
+        &mut self,
+        option: R,
+        search_key: K,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            let option = ff.upsert_option(option);
+            option.search_key = Some(search_key.into());
+            // the key may have changed, so any existing score is stale.
+            if option.score.take().is_some() {
+                ff.results_generation += 1;
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Updates the set of options to search by adding `(option, description)` pairs
+    /// from an iterator, returning what that changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_options_with_descriptions([("open", "Open a file"), ("quit", "Exit")]);
+    /// ```
+    pub fn push_options_with_descriptions<
+        T: 'a + IntoIterator<Item = (R, D)>,
+        R: Into<Cow<'a, str>>,
+        D: Into<Cow<'a, str>>,
+    >(
+        &mut self,
+        options: T,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            for (option, description) in options {
+                ff.upsert_option(option).description = Some(description.into());
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Builder method which sets search options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default().with_options(["one", "two", "three"]);
+    /// ```
+    pub fn with_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
+        mut self,
+        options: T,
+    ) -> Self {
+        self.set_options(options);
+        self
+    }
+
+    /// Builder method which sets the filter term.  When called before `with_options`,
+    /// options are scored against the filter in a single pass as they're added.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default()
+    ///     .with_filter("tw")
+    ///     .with_options(["one", "two", "three"]);
+    /// assert_eq!(ff.selection().unwrap().value, "two");
+    /// ```
+    pub fn with_filter<T: Into<Cow<'a, str>>>(mut self, filter: T) -> Self {
+        self.set_filter(filter);
+        self
+    }
+
+    /// Sets search options.  Equal options collapse into one entry, see
+    /// `try_set_options` to catch that.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_options(["one", "two", "three"]);
+    /// ```
+    pub fn set_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
+        &mut self,
+        options: T,
+    ) -> &mut Self {
+        // TODO be more efficient, keep any existing scores for overlapping keys.
+        // Maybe leverage `remove_options` when an efficient  version of that has
+        // been made.
+        if !self.matches.is_empty() {
+            self.matches.clear();
+            self.options_generation += 1;
+            self.results_generation += 1;
+            self.grouped = 0;
+        }
+        self.push_options(options);
+        self
+    }
+
+    /// Sets search options, keeping the scores of options already present, so only
+    /// added options are scored, returning what that changed.  Suits live sources,
+    /// e.g. pass a `HashMap`'s `values()` each time it updates.  Options are keyed by
+    /// value, so an edited value is a removed option plus an added one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut files = HashMap::from([(1, "main.rs"), (2, "lib.rs")]);
+    /// let mut ff = FuzzyFinder::default().with_filter("rs");
+    /// ff.set_options_keyed(files.values().copied());
+    /// files.insert(2, "data.rs");
+    /// ff.set_options_keyed(files.values().copied());
+    /// assert_eq!(ff.len(), 2);
+    /// assert!(ff.matches.contains_key("data.rs"));
+    /// ```
+    pub fn set_options_keyed<T: IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
+        &mut self,
+        options: T,
+    ) -> Change {
+        let options: Vec<Cow<'a, str>> = options.into_iter().map(Into::into).collect();
+        self.tracking_changes(|ff| {
+            let keep: HashSet<&str> = options.iter().map(AsRef::as_ref).collect();
+            let len = ff.matches.len();
+            let (grouped, results) = (&mut ff.grouped, &mut ff.results_generation);
+            ff.matches.retain(|value, option| {
+                let kept = keep.contains(value.as_ref());
+                if !kept && option.group.is_some() {
+                    *grouped -= 1;
+                }
+                if !kept && option.score.is_some() {
+                    *results += 1;
+                }
+                kept
+            });
+            if ff.matches.len() != len {
+                ff.options_generation += 1;
+            }
+            for option in options {
+                // existing options keep their scores.
+                ff.upsert_option(option);
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Sets search options, unless some are equal and would collapse into one entry,
+    /// as `set_options` silently does.  Then the options are left untouched, and the
+    /// error counts the duplicates, so callers labelling distinct items alike know to
+    /// disambiguate them.
+    ///
+    /// # Errors
+    ///
+    /// `OptionsError::Duplicates` if any option appears more than once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FuzzyFinder, OptionsError};
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// assert!(ff.try_set_options(["one", "two"]).is_ok());
+    /// assert_eq!(
+    ///     ff.try_set_options(["one", "two", "one"]).err(),
+    ///     Some(OptionsError::Duplicates {
+    ///         count: 1,
+    ///         first: "one".into()
+    ///     })
+    /// );
+    /// assert_eq!(ff.len(), 2);
+    /// ```
+    pub fn try_set_options<T: 'a + IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
+        &mut self,
+        options: T,
+    ) -> Result<&mut Self, OptionsError> {
+        let options: Vec<Cow<'a, str>> = options.into_iter().map(Into::into).collect();
+        let mut seen = HashSet::with_capacity(options.len());
+        let duplicates: Vec<&str> = options
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|option| !seen.insert(*option))
+            .collect();
+        if let Some(first) = duplicates.first() {
+            return Err(OptionsError::Duplicates {
+                count: duplicates.len(),
+                first: (*first).to_string(),
+            });
+        }
+        Ok(self.set_options(options))
+    }
+
+    /// Add an option matched against several fields, e.g. a contact's name and email,
+    /// returning what that changed.  The first field is the option's value, the rest
+    /// are displayed after it.  The best match across fields, boosted by
+    /// `set_field_weights`, ranks the option.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_fields(["Ada Lovelace", "ada@example.com"]);
+    /// ff.set_filter("example");
+    /// let selection = ff.selection().unwrap();
+    /// assert_eq!(selection.value, "Ada Lovelace");
+    /// assert_eq!(selection.field, 1);
+    /// ```
+    pub fn push_option_fields<T: IntoIterator<Item = R>, R: Into<Cow<'a, str>>>(
+        &mut self,
+        fields: T,
+    ) -> Change {
+        let mut fields = fields.into_iter();
+        let Some(value) = fields.next() else {
+            return Change::Unchanged;
+        };
+        self.tracking_changes(|ff| {
+            let option = ff.upsert_option(value);
+            option.fields = fields.map(Into::into).collect();
+            // the fields may have changed, so any existing score is stale.
+            if option.score.take().is_some() {
+                ff.results_generation += 1;
+            }
+            ff.options_generation += 1;
+            ff.update_matches(false);
+        })
+    }
+
+    /// Sets the boost added to a match's score by field index, 0 being the option's
+    /// value, e.g. so a name match outranks an email match.  Fields without a weight
+    /// get no boost.  Rescores options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.set_field_weights([50, 0]);
+    /// ```
+    pub fn set_field_weights<T: IntoIterator<Item = i64>>(&mut self, weights: T) -> &mut Self {
+        self.scorer.field_weights = weights.into_iter().collect();
+        self.update_matches(true);
+        self
+    }
+
+    /// Builder method to configure the matcher for `Algorithm::SkimV2`, the default,
+    /// e.g. its case sensitivity or bonuses, rescoring options.  The configured
+    /// matcher is kept and shared by every rescore.  Its `element_limit` bounds the
+    /// work of scoring an option, beyond which a cheaper greedy match is used, so
+    /// pathologically long options can't stall scoring.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default()
+    ///     .with_matcher_options(|matcher| matcher.ignore_case().element_limit(10_000))
+    ///     .with_options(["readme"])
+    ///     .with_filter("README");
+    /// assert!(ff.has_matches());
+    /// ```
+    pub fn with_matcher_options<F: FnOnce(SkimMatcherV2) -> SkimMatcherV2>(
+        mut self,
+        configure: F,
+    ) -> Self {
+        self.scorer.skim_matcher = Some(Arc::new(configure(SkimMatcherV2::default())));
+        self.update_matches(true);
+        self
+    }
+
+    /// Sets a boost added to the score of matches ending at an option's last char,
+    /// e.g. so "rs" ranks files with a ".rs" extension first.  Unlike a `$` anchor in
+    /// `MatchMode::Extended`, other matches still match.  Rescores.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["rsync.conf", "main.rs"]);
+    /// ff.set_suffix_bonus(100);
+    /// ff.set_filter("rs");
+    /// assert_eq!(ff.selection().unwrap().value, "main.rs");
+    /// assert_eq!(ff.ranked().len(), 2);
+    /// ```
+    pub fn set_suffix_bonus(&mut self, bonus: i64) -> &mut Self {
+        if bonus != self.scorer.suffix_bonus {
+            self.scorer.suffix_bonus = bonus;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Sets the fuzzy matching algorithm, rescoring options if it changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{Algorithm, FuzzyFinder};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["f_o_o", "xfoo"]);
+    /// ff.set_algorithm(Algorithm::Exact);
+    /// ff.set_filter("foo");
+    /// assert_eq!(ff.ranked().len(), 1);
+    /// assert_eq!(ff.selection().unwrap().value, "xfoo");
+    /// ```
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) -> &mut Self {
+        if algorithm != self.scorer.algorithm {
+            self.scorer.algorithm = algorithm;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Sets whether a multi-field option matches when any of its fields matches the
+    /// filter, as by default, or only when all do.  Either way it's ranked and
+    /// highlighted by its best field.  Rescores options if it changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FieldMatch, FuzzyFinder};
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_fields(["Ada Lovelace", "ada@example.com"]);
+    /// ff.set_filter("example");
+    /// assert!(ff.has_matches());
+    /// ff.set_field_match(FieldMatch::All);
+    /// assert!(!ff.has_matches());
+    /// ```
+    pub fn set_field_match(&mut self, field_match: FieldMatch) -> &mut Self {
+        if field_match != self.scorer.field_match {
+            self.scorer.field_match = field_match;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Add an option to search, with a `weight` added to its score when ranking,
+    /// returning what that changed.
+    /// With an empty filter every score is 0, so options are ordered by weight alone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_weighted("git status", 10);
+    /// ff.push_option_weighted("git stash", 2);
+    /// assert_eq!(ff.selection().unwrap().value, "git status");
+    /// ```
+    pub fn push_option_weighted<R: Into<Cow<'a, str>>>(
+        &mut self,
+        option: R,
+        weight: i64,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            let option = ff.upsert_option(option);
+            let reweighted = option.weight != weight && option.score.is_some();
+            option.weight = weight;
+            if reweighted {
+                ff.results_generation += 1;
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Sets the weight added to an option's score when ranking, re-ranking matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["one", "two"]);
+    /// ff.set_weight("two", 5);
+    /// assert_eq!(ff.selection().unwrap().value, "two");
+    /// ```
+    pub fn set_weight<R: AsRef<str>>(&mut self, option: R, weight: i64) -> &mut Self {
+        if let Some(entry) = self.matches.get_mut(option.as_ref()) {
+            entry.weight = weight;
+            self.resort_matches();
+        }
+        self
+    }
+
+    /// Add an option to search, with a `sort_key` breaking ties with equally scored
+    /// options, highest first, e.g. CPU usage in a process picker, returning what that
+    /// changed.  Match relevance still ranks first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option_with_sort_key("cargo", 5);
+    /// ff.push_option_with_sort_key("rustc", 90);
+    /// assert_eq!(ff.selection().unwrap().value, "rustc");
+    /// ```
+    pub fn push_option_with_sort_key<R: Into<Cow<'a, str>>>(
+        &mut self,
+        option: R,
+        sort_key: i64,
+    ) -> Change {
+        self.tracking_changes(|ff| {
+            let option = ff.upsert_option(option);
+            let rekeyed = option.sort_key != sort_key && option.score.is_some();
+            option.sort_key = sort_key;
+            if rekeyed {
+                ff.results_generation += 1;
+            }
+            ff.update_matches(false);
+        })
+    }
+
+    /// Sets an option's sort key, breaking ties with equally scored options, re-ranking
+    /// matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["one", "two"]);
+    /// ff.set_sort_key("two", 1);
+    /// assert_eq!(ff.selection().unwrap().value, "two");
+    /// ```
+    pub fn set_sort_key<R: AsRef<str>>(&mut self, option: R, sort_key: i64) -> &mut Self {
+        if let Some(entry) = self.matches.get_mut(option.as_ref()) {
+            entry.sort_key = sort_key;
+            self.resort_matches();
+        }
+        self
+    }
+
+    /// Adds file paths as options, converted to strings lossily.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_paths([PathBuf::from("src/lib.rs"), PathBuf::from("Cargo.toml")]);
+    /// assert_eq!(ff.len(), 2);
+    /// ```
+    pub fn push_paths<T: IntoIterator<Item = P>, P: AsRef<Path>>(&mut self, paths: T) -> Change {
+        let paths: Vec<String> = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_string_lossy().into_owned())
+            .collect();
+        self.push_options(paths)
+    }
+
+    /// Sets chars left out of the filter and options before matching, so they needn't
+    /// be typed, e.g. `['/', '\\']` so "srcmain" finds "src/main" as a substring.
+    /// Highlighting still applies to options as pushed.  Rescores.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::{FuzzyFinder, MatchMode};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["src/main.rs"]);
+    /// ff.set_match_mode(MatchMode::Substring);
+    /// ff.set_filter("srcmain");
+    /// assert!(!ff.has_matches());
+    /// ff.set_optional_separators(['/', '\\']);
+    /// assert_eq!(ff.selection().unwrap().indices, vec![0, 1, 2, 4, 5, 6, 7]);
+    /// ```
+    pub fn set_optional_separators<T: IntoIterator<Item = char>>(
+        &mut self,
+        separators: T,
+    ) -> &mut Self {
+        self.scorer.optional_separators = separators.into_iter().collect();
+        self.update_matches(true);
+        self
+    }
+
+    /// Sets whether options are matched as paths, by file name where it matches and
+    /// by the whole path otherwise, so a filter matching a file's name is scored and
+    /// highlighted there rather than in its directories.  Rescores.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["lib-dir/xlib.rs"]);
+    /// ff.set_filter("lib");
+    /// assert_eq!(ff.selection().unwrap().indices, vec![0, 1, 2]);
+    /// ff.set_path_matching(true);
+    /// assert_eq!(ff.selection().unwrap().indices, vec![9, 10, 11]);
+    /// ```
+    pub fn set_path_matching(&mut self, paths: bool) -> &mut Self {
+        if paths != self.scorer.paths {
+            self.scorer.paths = paths;
+            self.update_matches(true);
+        }
+        self
+    }
+
+    /// Pins an option above unpinned ones whenever it matches the filter, e.g. a
+    /// "Create new file…" entry.  Pinned options are ranked by score among themselves,
+    /// and are still hidden when they don't match.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["new file", "notes.txt"]);
+    /// ff.pin_option("new file");
+    /// ff.set_filter("n");
+    /// assert_eq!(ff.selection().unwrap().value, "new file");
+    /// assert!(ff.is_pinned("new file"));
+    /// ```
+    pub fn pin_option<R: AsRef<str>>(&mut self, option: R) -> &mut Self {
+        self.set_pinned(option.as_ref(), true)
+    }
+
+    /// Unpins an option, so it's ranked by score alone again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["new file"]);
+    /// ff.pin_option("new file").unpin_option("new file");
+    /// assert!(!ff.is_pinned("new file"));
+    /// ```
+    pub fn unpin_option<R: AsRef<str>>(&mut self, option: R) -> &mut Self {
+        self.set_pinned(option.as_ref(), false)
+    }
+
+    /// Whether an option is pinned, `false` if there's no such option.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default().with_options(["one"]);
+    /// assert!(!ff.is_pinned("one"));
+    /// ```
+    pub fn is_pinned<R: AsRef<str>>(&self, option: R) -> bool {
+        self.matches
+            .get(option.as_ref())
+            .map_or(false, |option| option.pinned)
+    }
+
+    /// Pins or unpins an option, re-ranking matches if that changed anything.
+    fn set_pinned(&mut self, option: &str, pinned: bool) -> &mut Self {
+        if let Some(entry) = self.matches.get_mut(option) {
+            if entry.pinned != pinned {
+                entry.pinned = pinned;
+                self.resort_matches();
+            }
+        }
+        self
+    }
+
+    /// Marks an option as used, e.g. a command run this session, so a `FuzzyList` can
+    /// dim it with its `used_style`.  The mark lasts as long as the option, whatever
+    /// the filter, and doesn't affect ranking; combine with `set_weight` for that.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["build", "test"]);
+    /// ff.set_used("build", true);
+    /// ff.set_filter("t");
+    /// assert!(ff.is_used("build"));
+    /// ```
+    pub fn set_used<R: AsRef<str>>(&mut self, option: R, used: bool) -> &mut Self {
+        if let Some(entry) = self.matches.get_mut(option.as_ref()) {
+            entry.used = used;
+        }
+        self
+    }
+
+    /// Whether an option is marked used, `false` if there's no such option.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let ff = FuzzyFinder::default().with_options(["build"]);
+    /// assert!(!ff.is_used("build"));
+    /// ```
+    pub fn is_used<R: AsRef<str>>(&self, option: R) -> bool {
+        self.matches
+            .get(option.as_ref())
+            .map_or(false, |option| option.used)
+    }
+
+    /// Add an option to search, returning what that changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_option("hello");
+    /// ```
+    pub fn push_option<R: Into<Cow<'a, str>>>(&mut self, option: R) -> Change {
+        self.tracking_changes(|ff| {
+            ff.upsert_option(option);
+            ff.update_matches(false);
+        })
+    }
+
+    /// Runs `update` with rescoring deferred until it returns, so e.g. pushing many
+    /// options and setting a filter rescans options once rather than per call.
+    /// Returns what the batch changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.batch(|ff| {
+    ///     for option in ["abc", "bcd", "cde"] {
+    ///         ff.push_option(option);
+    ///     }
+    ///     ff.set_filter("cd");
+    /// });
+    /// assert_eq!(ff.selection().unwrap().value, "cde");
+    /// ```
+    pub fn batch<F: FnOnce(&mut Self)>(&mut self, update: F) -> Change {
+        self.tracking_changes(|ff| {
+            ff.begin_batch();
+            update(ff);
+            ff.end_batch();
+        })
+    }
+
+    /// Starts deferring rescoring, e.g. while a stream of options arrives, until the
+    /// matching `end_batch`.  Options pushed meanwhile aren't scored, so don't show
+    /// as matches, until then.  Batches nest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.begin_batch();
+    /// for i in 0..10_000 {
+    ///     ff.push_option(format!("option {i}"));
+    /// }
+    /// assert!(!ff.has_matches());
+    /// ff.end_batch();
+    /// assert!(ff.has_matches());
+    /// ```
+    pub fn begin_batch(&mut self) -> &mut Self {
+        self.batch_depth += 1;
+        self
+    }
+
+    /// Ends a batch started by `begin_batch`, rescoring and sorting once for everything
+    /// deferred if it was the outermost.  Without a batch in progress, does nothing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.begin_batch().push_options(["abc", "bcd"]);
+    /// ff.end_batch();
+    /// assert_eq!(ff.ranked().len(), 2);
+    /// ```
+    pub fn end_batch(&mut self) -> &mut Self {
+        if self.batch_depth == 0 {
+            return self;
+        }
+        self.batch_depth -= 1;
+        if self.batch_depth == 0 {
+            if let Some(new_filter_term) = self.pending.take() {
+                self.update_matches(new_filter_term);
+            }
+        }
+        self
+    }
+
+    /// Whether rescoring has been deferred by a batch in progress, e.g. for options
+    /// pushed since it began.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.begin_batch().push_option("abc");
+    /// assert!(ff.needs_update());
+    /// ff.update();
+    /// assert!(!ff.needs_update());
+    /// ```
+    pub fn needs_update(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Performs any rescoring deferred by a batch in progress, without ending it, so
+    /// an app can pay the scoring cost when it chooses, e.g. once per frame.  Returns
+    /// what that changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.begin_batch().push_options(["abc", "bcd"]);
+    /// ff.update();
+    /// assert_eq!(ff.ranked().len(), 2);
+    /// ```
+    pub fn update(&mut self) -> Change {
+        let Some(new_filter_term) = self.pending.take() else {
+            return Change::Unchanged;
+        };
+        self.tracking_changes(|ff| {
+            let batch_depth = std::mem::take(&mut ff.batch_depth);
+            ff.update_matches(new_filter_term);
+            ff.batch_depth = batch_depth;
+        })
+    }
+
+    /// Runs `update`, reporting what it changed about the rendered results.
+    pub(crate) fn tracking_changes<F: FnOnce(&mut Self)>(&mut self, update: F) -> Change {
+        let results = self.results_generation;
+        let selected = (self.selected_index(), self.selected_key.clone());
+        update(self);
+        if self.results_generation != results {
+            Change::Results
+        } else if (self.selected_index(), self.selected_key.as_ref())
+            != (selected.0, selected.1.as_ref())
+        {
+            Change::Selection
+        } else {
+            Change::Unchanged
+        }
+    }
+
+    /// Adds an option to search, or finds the equal option already present, without
+    /// updating.
+    fn upsert_option<R: Into<Cow<'a, str>>>(&mut self, option: R) -> &mut FuzzyOption<'a> {
+        let insertion = self.insertions;
+        self.insertions += 1;
+        let (sanitizer, max_option_len) = (self.sanitizer, self.max_option_len);
+        let unscored = self.lazy_window.is_some();
+        // keep existing score if entry exists.
+        match self.matches.entry(option.into()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                self.options_generation += 1;
+                let (truncated, styles) = display_option(entry.key(), sanitizer, max_option_len);
+                entry.insert(FuzzyOption {
+                    insertion,
+                    unscored,
+                    truncated,
+                    styles,
+                    ..FuzzyOption::default()
+                })
+            }
+        }
+    }
+
+    /// Rescores just the options keyed by `keys` against the current filter, e.g.
+    /// after editing their fields in place through `matches`, and re-ranks them,
+    /// returning what that changed.  Much cheaper than rescoring every option.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default()
+    ///     .with_filter("mail")
+    ///     .with_options(["ada", "bob"]);
+    /// ff.matches.get_mut("bob").unwrap().fields = vec!["bob@mail.com".into()];
+    /// ff.rescore_options(["bob"]);
+    /// assert_eq!(ff.selection().unwrap().value, "bob");
+    /// ```
+    pub fn rescore_options<I, K>(&mut self, keys: I) -> Change
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<str>,
+    {
+        self.tracking_changes(|ff| {
+            let filter = ff.normalized_filter().into_owned();
+            let matcher = ff.scorer.matcher();
+            for key in keys {
+                let Some(option) = ff.matches.get_mut(key.as_ref()) else {
+                    continue;
+                };
+                let score = if filter.is_empty() {
+                    empty_filter_score(!ff.scorer.hide_all_on_empty)
+                } else {
+                    ff.scorer
+                        .score_option(matcher.as_ref(), key.as_ref(), option, &filter)
+                };
+                if option.rescore(score) {
+                    ff.results_generation += 1;
+                }
+            }
+            ff.sort_matches();
+        })
+    }
+
+    /// Removes an option.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_options(["hello", "friend"]);
+    /// ff.remove_option("hello");
+    /// ```
+    pub fn remove_option<R: AsRef<str>>(&mut self, key: R) {
+        if let Some(option) = self.matches.shift_remove(key.as_ref()) {
+            self.forget(&option);
+            self.update_score_stats();
+        }
+    }
+
+    /// Removes multiple options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default();
+    /// ff.push_options(["hello", "my", "old", "friend"]);
+    /// ff.remove_options(["my", "old"]);
+    /// ```
+    pub fn remove_options<T: 'a + IntoIterator<Item = R>, R: AsRef<str>>(&mut self, keys: T) {
+        // TODO something smarter, this will O(n) shift all entries in `self.matches`
+        // for each key.  Will in certain cases be better to just `self.matches.remove`
+        // followed by a sort.
+        for key in keys {
+            self.remove_option(key);
+        }
+    }
+
+    /// Removes an option in O(1) by moving another into its place, without re-ranking.
+    /// Display order is disturbed until the next `update`, `set_filter` or push, which
+    /// re-sorts: `needs_update` reports it.  Matches still come before non-matches, and
+    /// the selection stays on its option, or at its position if that was removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// ff.swap_remove_option("abc");
+    /// assert!(ff.needs_update());
+    /// ff.update();
+    /// assert_eq!(ff.best_match().unwrap().value, "bcd");
+    /// ```
+    pub fn swap_remove_option<R: AsRef<str>>(&mut self, key: R) {
+        self.swap_remove_options([key]);
+    }
+
+    /// Removes multiple options, each in O(1) as `swap_remove_option` does, leaving
+    /// them all to be re-ranked by the next update.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["hello", "my", "old", "friend"]);
+    /// ff.swap_remove_options(["my", "old"]);
+    /// ff.update();
+    /// let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+    /// assert_eq!(ranked, ["hello", "friend"]);
+    /// ```
+    pub fn swap_remove_options<T: IntoIterator<Item = R>, R: AsRef<str>>(&mut self, keys: T) {
+        let selected = self.selected_index();
+        let selected_key = selected
+            .and_then(|index| self.matches.get_index(index))
+            .map(|(key, _)| key.to_string());
+        let mut removed = false;
+        for key in keys {
+            let Some(index) = self.matches.get_index_of(key.as_ref()) else {
+                continue;
+            };
+            // matches come first, then options scored not to match, then the unscored.
+            let ends = [
+                self.matches
+                    .partition_point(|_, option| option.score.is_some()),
+                self.matches.partition_point(|_, option| !option.unscored),
+            ];
+            if let Some((_, option)) = self.matches.swap_remove_index(index) {
+                self.forget(&option);
+            }
+            // the last option moved into the gap, so swap it on to the end of each of
+            // those it doesn't belong in.
+            let mut at = index;
+            for (kind, end) in ends.into_iter().enumerate() {
+                let Some((_, moved)) = self.matches.get_index(at) else {
+                    break;
+                };
+                let moved_kind = if moved.score.is_some() {
+                    0
+                } else {
+                    1 + usize::from(moved.unscored)
+                };
+                if at < end && moved_kind > kind {
+                    self.matches.swap_indices(at, end - 1);
+                    at = end - 1;
+                }
+            }
+            removed = true;
+        }
+        if !removed {
+            return;
+        }
+        self.pending.get_or_insert(false);
+        match selected_key.and_then(|key| self.matches.get_index_of(key.as_str())) {
+            Some(index) => self.set_selected(Some(index)),
+            None => {
+                if let Some(index) = selected {
+                    let matched = self
+                        .matches
+                        .partition_point(|_, option| option.score.is_some());
+                    self.set_selected(matched.checked_sub(1).map(|last| index.min(last)));
+                }
+            }
+        }
+    }
+
+    /// Accounts for `option` having been removed.
+    fn forget(&mut self, option: &FuzzyOption) {
+        self.options_generation += 1;
+        if option.score.is_some() {
+            self.results_generation += 1;
+        }
+        if option.group.is_some() {
+            self.grouped -= 1;
+        }
+    }
+
+    /// Keeps only the options for which `f` returns true, e.g. dropping files which no
+    /// longer exist, in one pass.  Ranking is unchanged, and the selection follows
+    /// its option if kept, otherwise staying at the same position.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["a.rs", "b.txt", "c.rs"]);
+    /// ff.select_next().select_next();
+    /// ff.retain(|option| option.ends_with(".rs"));
+    /// assert_eq!(ff.matches.len(), 2);
+    /// assert_eq!(ff.selection().unwrap().value, "c.rs");
+    /// ```
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, mut f: F) {
+        let selected = self.selected_index();
+        let selected_key = selected
+            .and_then(|index| self.matches.get_index(index))
+            .map(|(key, _)| key.to_string());
+        let len = self.matches.len();
+        let (grouped, results) = (&mut self.grouped, &mut self.results_generation);
+        self.matches.retain(|key, option| {
+            let keep = f(key);
+            if !keep && option.group.is_some() {
+                *grouped -= 1;
+            }
+            if !keep && option.score.is_some() {
+                *results += 1;
+            }
+            keep
+        });
+        if self.matches.len() == len {
+            return;
+        }
+        self.options_generation += 1;
+        match selected_key.and_then(|key| self.matches.get_index_of(key.as_str())) {
+            Some(index) => self.set_selected(Some(index)),
+            None => {
+                if let Some(index) = selected {
+                    self.select(index);
+                }
+            }
+        }
+        self.update_score_stats();
+    }
+
+    /// Scores options only until `window` of them match, scoring more a chunk at a
+    /// time as the selection moves within `window` of the last match, e.g. for lists of
+    /// millions where only the top results are ever seen.  Options ranked highest for
+    /// the previous filter are scored first.
+    ///
+    /// This trades accuracy for speed: matches are only the best of the options scored
+    /// so far, so the best match overall may be missing until the user scrolls far
+    /// enough, and newly scored matches are merged in among those already shown,
+    /// without sorting the rest.  Scoring
+    /// in the background with `set_filter_in_background` still scores every option.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let options: Vec<String> = (0..100_000).map(|i| format!("option {i}")).collect();
+    /// let mut ff = FuzzyFinder::default().with_options(options.iter().map(String::as_str));
+    /// ff.set_lazy_scoring(50);
+    /// ff.set_filter("opt");
+    /// assert!(ff.ranked().len() < options.len());
+    /// ```
+    pub fn set_lazy_scoring(&mut self, window: usize) -> &mut Self {
+        self.lazy_window = Some(window.max(1));
+        self.update_matches(true);
+        self
+    }
+
+    /// Scores options not yet scored lazily, a chunk at a time, until `target` options
+    /// match, ranking each chunk's matches among those already ranked.  Returns how
+    /// many were scored.
+    fn score_lazily(&mut self, matcher: &dyn FuzzyMatcher, filter: &str, target: usize) -> usize {
+        let mut scored = 0;
+        loop {
+            // matches come first, then options scored not to match, then the unscored.
+            let ranked = self
+                .matches
+                .partition_point(|_, option| option.score.is_some());
+            let start = self.matches.partition_point(|_, option| !option.unscored);
+            let end = (start + LAZY_CHUNK).min(self.matches.len());
+            if ranked >= target || start == end {
+                return scored;
+            }
+            let scoring = &self.scorer;
+            if let Some(chunk) = self.matches.get_range_mut(start..end) {
+                chunk.par_iter_mut().for_each(|(value, option)| {
+                    option.rescore(scoring.score_option(matcher, value, option, filter));
+                });
+            }
+            scored += end - start;
+            // swap the chunk's matches in behind those already ranked.
+            let mut found = ranked;
+            for index in start..end {
+                if self.matches[index].score.is_some() {
+                    self.matches.swap_indices(found, index);
+                    found += 1;
+                }
+            }
+            if found > ranked {
+                self.results_generation += 1;
+                self.merge_ranked(ranked..found);
+            }
+        }
+    }
+
+    /// Ranks the matches at `added`, just after the ranked matches, among them.  Only
+    /// the matches ranked below the best of those added are moved.
+    fn merge_ranked(&mut self, added: Range<usize>) {
+        if self.sort_by.is_some() || self.grouped > 0 {
+            // `rank_lazily` reorders every match instead.
+            return;
+        }
+        let cmp = self.rank_comparator();
+        let mut new: Vec<usize> = added.clone().collect();
+        new.sort_unstable_by(|&i, &j| cmp(&self.matches[i], &self.matches[j]));
+        let best = &self.matches[new[0]];
+        let from = self.matches.get_range(..added.start).map_or(0, |ranked| {
+            ranked.partition_point(|_, option| cmp(option, best) == Ordering::Less)
+        });
+        // the options merged into each position from `from`.
+        let mut sources = Vec::with_capacity(added.end - from);
+        let (mut old, mut new) = ((from..added.start).peekable(), new.into_iter().peekable());
+        while let (Some(&i), Some(&j)) = (old.peek(), new.peek()) {
+            if cmp(&self.matches[j], &self.matches[i]) == Ordering::Less {
+                sources.push(j);
+                new.next();
+            } else {
+                sources.push(i);
+                old.next();
+            }
+        }
+        sources.extend(old.chain(new));
+        // apply the permutation a cycle at a time.
+        let mut placed = vec![false; sources.len()];
+        for first in 0..sources.len() {
+            let mut at = first;
+            while !placed[at] {
+                placed[at] = true;
+                let source = sources[at] - from;
+                if source == first {
+                    break;
+                }
+                self.matches.swap_indices(from + at, from + source);
+                at = source;
+            }
+        }
+    }
+
+    /// Scores more options lazily, until `target` options match, and ranks them.
+    fn expand_lazily(&mut self, target: usize) {
+        if let Some(pool) = self.pool_to_enter() {
+            return pool.install(|| self.expand_lazily(target));
+        }
+        let start = Instant::now();
+        let filter = self.normalized_filter().into_owned();
+        let scored = self.rank_lazily(&filter, target);
+        self.report_metrics(start, scored);
+    }
+
+    /// Scores options lazily until `target` match, ranking them without sorting the
+    /// rest, unless a `set_sort_by` comparator or groups need every match reordered.
+    /// Returns how many were scored.
+    fn rank_lazily(&mut self, filter: &str, target: usize) -> usize {
+        let matcher = self.scorer.matcher();
+        self.reranking(|ff| {
+            let scored = ff.score_lazily(matcher.as_ref(), filter, target);
+            if ff.sort_by.is_some() || ff.grouped > 0 {
+                ff.reorder_matches();
+            }
+            scored
+        })
+    }
+
+    /// Computes new scores for all options if `new_filter_term` is true.
+    /// Otherwise competes scores for all options who haven't had a calculation
+    /// yet against the current filter.
+    fn update_matches(&mut self, new_filter_term: bool) {
+        if self.batch_depth > 0 {
+            // the batch rescores once it ends.
+            self.pending = Some(new_filter_term || self.pending == Some(true));
+            return;
+        }
+        if let Some(pool) = self.pool_to_enter() {
+            return pool.install(|| self.update_matches(new_filter_term));
+        }
+        if new_filter_term {
+            self.next_generation();
+        }
+        let start = Instant::now();
+        let filter = normalize_filter(
+            &self.filter,
+            !self.preserve_filter_whitespace,
+            self.min_token_len,
+            &self.scorer,
+        );
+        if new_filter_term {
+            self.scored_filter = filter.to_string();
+        }
+        if filter.is_empty() {
+            // everything matches an empty filter, no need to consult the matcher.
+            let show_all = !self.scorer.hide_all_on_empty;
+            let changed = AtomicBool::new(false);
+            let scored = self
+                .matches
+                .par_iter_mut()
+                .filter(|(_, option)| new_filter_term || option.score.is_none())
+                .map(|(_, option)| {
+                    if option.rescore(empty_filter_score(show_all)) {
+                        changed.store(true, atomic::Ordering::Relaxed);
+                    }
+                })
+                .count();
+            self.note_rescored(changed);
+            self.sort_matches();
+            self.report_metrics(start, scored);
+            return;
+        }
+        if let Some(window) = self.lazy_window {
+            if new_filter_term {
+                // left in their current order, so options ranked for the previous
+                // filter are scored first.
+                self.matches.par_iter_mut().for_each(|(_, option)| {
+                    option.score = None;
+                    option.unscored = true;
+                });
+                self.results_generation += 1;
+            }
+            let filter = filter.into_owned();
+            let target = self.selected_index().unwrap_or_default() + window;
+            let scored = self.rank_lazily(&filter, target);
+            self.report_metrics(start, scored);
+            return;
+        }
+        let matcher = self.scorer.matcher();
+
+        // TODO None matches were inserted last, so we should be able to iterate
+        // from the end and stop early.  But I couldn't quite find the right
+        // early-stopping option for an IndexedParallesIterator
+        // iter = iter.rev().take_any_while... race behavior is not ideal
+        let changed = AtomicBool::new(false);
+        let scored = self
+            .matches
+            .par_iter_mut()
+            .filter(|(_, option)| new_filter_term || option.score.is_none())
+            .map(|(value, option)| {
+                let score = self
+                    .scorer
+                    .score_option(matcher.as_ref(), value, option, &filter);
+                if option.rescore(score) {
+                    changed.store(true, atomic::Ordering::Relaxed);
+                }
+            })
+            .count();
+        self.note_rescored(changed);
+
+        self.sort_matches();
+        self.report_metrics(start, scored);
+    }
+
+    /// Bumps `results_generation` if a scoring pass flagged `changed`.
+    fn note_rescored(&mut self, changed: AtomicBool) {
+        if changed.into_inner() {
+            self.results_generation += 1;
+        }
+    }
+
+    /// Rescores only the options matching the previously scored filter, which the
+    /// current filter extends, so nothing else can match it.
+    fn narrow_matches(&mut self) {
+        // options not yet scored lazily may match too.
+        if self.batch_depth > 0 || self.lazy_window.is_some() {
+            return self.update_matches(true);
+        }
+        if let Some(pool) = self.pool_to_enter() {
+            return pool.install(|| self.narrow_matches());
+        }
+        self.next_generation();
+        let start = Instant::now();
+        let filter = normalize_filter(
+            &self.filter,
+            !self.preserve_filter_whitespace,
+            self.min_token_len,
+            &self.scorer,
+        );
+        self.scored_filter = filter.to_string();
+        let matcher = self.scorer.matcher();
+        let changed = AtomicBool::new(false);
+        let scored = self
+            .matches
+            .par_iter_mut()
+            .filter(|(_, option)| option.score.is_some())
+            .map(|(value, option)| {
+                let score = self
+                    .scorer
+                    .score_option(matcher.as_ref(), value, option, &filter);
+                if option.rescore(score) {
+                    changed.store(true, atomic::Ordering::Relaxed);
+                }
+            })
+            .count();
+        self.note_rescored(changed);
+        self.sort_matches();
+        self.report_metrics(start, scored);
+    }
+
+    /// Reorders ranked matches by the comparator set with `set_sort_by`, if any,
+    /// keeping their ranking among those it considers equal.
+    fn apply_sort_by(&mut self) {
+        let Some(ref sort_by) = self.sort_by else {
+            return;
+        };
+        let entries: Vec<FuzzyListEntry> = (0..self.matched_len())
+            .map_while(|index| self.entry(index))
+            .collect();
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        // stable, so ties keep their ranking.
+        order.sort_by(|&i, &j| sort_by(&entries[i], &entries[j]));
+        let mut options: Vec<_> = std::mem::take(&mut self.matches)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.matches.reserve(options.len());
+        for index in order.iter().copied().chain(order.len()..options.len()) {
+            if let Some((value, option)) = options[index].take() {
+                self.matches.insert(value, option);
+            }
+        }
+    }
+
+    /// Gathers ranked matches by group, if any option has one, keeping their order
+    /// within each group.  Groups rank by their best match, which the sort put first.
+    fn group_matches(&mut self) {
+        if self.grouped == 0 {
+            return;
+        }
+        let mut ranks: HashMap<String, usize> = HashMap::new();
+        let mut ungrouped = None;
+        for option in self
+            .matches
+            .values()
+            .take_while(|option| option.score.is_some())
+        {
+            let next = ranks.len() + usize::from(ungrouped.is_some());
+            match option.group.as_deref() {
+                Some(group) if !ranks.contains_key(group) => {
+                    ranks.insert(group.to_owned(), next);
+                }
+                None if ungrouped.is_none() => ungrouped = Some(next),
+                _ => {}
+            }
+        }
+        // stable, so matches keep their ranking within a group.
+        self.matches
+            .sort_by_cached_key(|_, option| match option.score {
+                Some(_) => option
+                    .group
+                    .as_deref()
+                    .map_or(ungrouped, |group| ranks.get(group).copied())
+                    .unwrap_or(usize::MAX),
+                None => usize::MAX,
+            });
+    }
+
+    /// Re-ranks matches after what orders them, rather than their scores, changed.
+    fn resort_matches(&mut self) {
+        self.results_generation += 1;
+        self.sort_matches();
+    }
+
+    /// Orders matches first, ranked pinned first, then by weighted score, best first,
+    /// then by sort key, then by insertion order, unless input order is preserved.
+    /// Then applies any `set_sort_by` comparator and gathers groups, updates
+    /// `score_stats`, and restores the selection by the selection policy.
+    fn sort_matches(&mut self) {
+        if self.batch_depth > 0 {
+            // the batch sorts once it ends.
+            self.pending.get_or_insert(false);
+            return;
+        }
+        if let Some(pool) = self.pool_to_enter() {
+            return pool.install(|| self.sort_matches());
+        }
+        self.reranking(Self::reorder_matches);
+    }
+
+    /// Sorts every option by the ranking, then applies any `set_sort_by` comparator
+    /// and gathers groups.
+    fn reorder_matches(&mut self) {
+        let cmp = self.rank_comparator();
+        if self.sequential_sort {
+            self.matches.sort_unstable_by(|_, v1, _, v2| cmp(v1, v2));
+        } else {
+            self.matches
+                .par_sort_unstable_by(|_, v1, _, v2| cmp(v1, v2));
+        }
+        self.apply_sort_by();
+        self.group_matches();
+    }
+
+    /// Ordering of options by the ranking, or by input order if that's preserved.
+    fn rank_comparator(&self) -> fn(&FuzzyOption<'a>, &FuzzyOption<'a>) -> Ordering {
+        if self.scorer.preserve_input_order {
+            // matches first, in the order they were pushed.
+            |v1, v2| {
+                (v1.score.is_none(), v1.unscored, v1.insertion).cmp(&(
+                    v2.score.is_none(),
+                    v2.unscored,
+                    v2.insertion,
+                ))
+            }
+        } else {
+            FuzzyOption::cmp_rank
+        }
+    }
+
+    /// Runs `reorder` on the matches, then updates `score_stats` and restores the
+    /// selection by the selection policy.
+    fn reranking<T, F: FnOnce(&mut Self) -> T>(&mut self, reorder: F) -> T {
+        let selected = self.state.selected();
+        let selected_key = self.selected_key.take();
+        let previous_len = self.score_stats.as_ref().map_or(0, |stats| stats.count);
+        let result = reorder(self);
+        self.update_score_stats();
+
+        // TODO only if some change
+        self.restore_selection(selected, selected_key, previous_len);
+        result
+    }
+
+    /// Selects an option after re-ranking, following the selection policy, given the
+    /// previous selection and how many options matched before.
+    fn restore_selection(
+        &mut self,
+        selected: Option<usize>,
+        selected_key: Option<String>,
+        previous_len: usize,
+    ) {
+        let len = self.score_stats.as_ref().map_or(0, |stats| stats.count);
+        let index =
+            match self.selection_policy {
+                SelectionPolicy::ResetToTop => None,
+                SelectionPolicy::KeepByValue => selected_key
+                    .and_then(|key| self.matches.get_index_of(key.as_str()))
+                    .filter(|&index| index < len),
+                SelectionPolicy::Proportional => selected
+                    .filter(|_| previous_len > 1 && len > 0)
+                    .map(|selected| {
+                        // rounded to the nearest row.
+                        let scaled =
+                            (selected * (len - 1) + (previous_len - 1) / 2) / (previous_len - 1);
+                        scaled.min(len - 1)
+                    }),
+            };
+        match index {
+            Some(index) => self.set_selected(Some(index)),
+            None => {
+                self.reset_selection();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remove_option() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_options(["hello", "friend"]);
+        assert!(ff.matches.contains_key("hello"));
+        ff.remove_option("hello");
+        assert!(!ff.matches.contains_key("hello"));
+    }
+
+    #[test]
+    fn set_same_filter_keeps_selection() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_options(["abc", "abd", "abe"]);
+        ff.set_filter("ab");
+        ff.select_next();
+        assert_eq!(ff.state.selected(), Some(1));
+        ff.set_filter("ab".to_string());
+        assert_eq!(ff.state.selected(), Some(1));
+        ff.set_filter("abd");
+        assert_eq!(ff.state.selected(), Some(0));
+    }
+
+    #[test]
+    fn background_scoring_applies_latest_filter() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "xyz"]);
+        ff.set_filter_in_background("a");
+        ff.set_filter_in_background("xy");
+        while !ff.poll_results() {
+            std::thread::yield_now();
+        }
+        assert!(ff.matches["xyz"].score.is_some());
+        assert!(ff.matches["abc"].score.is_none());
+        assert_eq!(ff.selection().map(|s| s.value), Some("xyz"));
+    }
+
+    #[test]
+    fn len_and_has_matches() {
+        let mut ff = FuzzyFinder::default();
+        assert!(ff.is_empty());
+        assert!(!ff.has_matches());
+        ff.push_options(["abc", "bcd", "cde"]);
+        assert_eq!(ff.len(), 3);
+        assert!(ff.has_matches());
+        ff.set_filter("zzz");
+        assert_eq!(ff.len(), 3);
+        assert!(!ff.has_matches());
+    }
+
+    #[test]
+    fn descriptions_are_not_matched() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_options_with_descriptions([("open", "Open a file"), ("quit", "Exit")]);
+        ff.set_filter("file");
+        assert!(!ff.has_matches());
+        ff.set_filter("qu");
+        let selection = ff.selection().unwrap();
+        assert_eq!(selection.value, "quit");
+        assert_eq!(selection.description, Some("Exit"));
+    }
+
+    #[test]
+    fn paths_match_file_name_first() {
+        let mut ff = FuzzyFinder::default();
+        ff.set_path_matching(true);
+        ff.push_paths([Path::new("ab/cab")]);
+        ff.set_filter("ab");
+        assert_eq!(ff.selection().unwrap().indices, vec![4, 5]);
+        ff.set_filter("abc");
+        assert_eq!(ff.selection().unwrap().indices, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn substring_mode() {
+        let mut ff = FuzzyFinder::default().with_options(["xab", "a_b", "ab", "AB"]);
+        ff.set_match_mode(MatchMode::Substring).set_filter("ab");
+        assert!(ff.matches["a_b"].score.is_none());
+        assert!(ff.matches["AB"].score.is_none());
+        assert_eq!(ff.matches.get_index(0).unwrap().0, "ab");
+        assert_eq!(ff.matches.get_index(1).unwrap().0, "xab");
+        assert_eq!(
+            ff.matches["xab"].score.as_ref().unwrap().indices,
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn exact_algorithm_matches_substrings() {
+        let mut ff = FuzzyFinder::default().with_options(["xab", "a_b", "ab", "AB"]);
+        ff.set_algorithm(Algorithm::Exact);
+        ff.set_filter("ab");
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked, ["ab", "xab"]);
+        assert_eq!(ff.ranked()[1].indices, vec![1, 2]);
+    }
+
+    #[test]
+    fn exact_algorithm_exact_matches_are_case_sensitive() {
+        let mut ff = FuzzyFinder::default().with_options(["Foo", "foo"]);
+        ff.set_filter("foo");
+        assert!(ff.is_exact_match("Foo"));
+        ff.set_algorithm(Algorithm::Exact);
+        assert!(!ff.is_exact_match("Foo"));
+        assert!(ff.is_exact_match("foo"));
+        assert!(ff.selection().unwrap().is_exact());
+    }
+
+    #[test]
+    fn exact_matches() {
+        let mut ff = FuzzyFinder::default().with_options(["Foo", "food"]);
+        ff.set_filter("foo");
+        assert!(ff.is_exact_match("Foo"));
+        assert!(!ff.is_exact_match("food"));
+        ff.set_filter("Foo");
+        assert!(ff.selection().unwrap().is_exact());
+        ff.set_filter("fo");
+        assert!(!ff.selection().unwrap().is_exact());
+        ff.set_match_mode(MatchMode::Substring).set_filter("foo");
+        assert_eq!(ff.selection().unwrap().value, "food");
+        assert!(!ff.selection().unwrap().is_exact());
+    }
+
+    #[test]
+    fn with_filter_scores_options() {
+        let ff = FuzzyFinder::default()
+            .with_filter("thr")
+            .with_options(["one", "two", "three"]);
+        assert!(ff.matches["three"].score.is_some());
+        assert!(ff.matches["one"].score.is_none());
+    }
+
+    #[test]
+    fn weights_boost_ranking() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_weighted("a", 1);
+        ff.push_option_weighted("b", 3);
+        ff.push_option_weighted("c", 2);
+        let order: Vec<_> = ff.matches.keys().map(ToString::to_string).collect();
+        assert_eq!(order, vec!["b", "c", "a"]);
+        ff.set_weight("a", 4);
+        assert_eq!(ff.selection().unwrap().value, "a");
+    }
+
+    #[test]
+    fn empty_filter_keeps_insertion_order() {
+        let mut ff = FuzzyFinder::default().with_options(["c", "a", "b"]);
+        ff.push_option_weighted("d", 1);
+        ff.set_filter("a");
+        ff.clear_filter();
+        let order: Vec<_> = ff.matches.keys().map(ToString::to_string).collect();
+        assert_eq!(order, vec!["d", "c", "a", "b"]);
+    }
+
+    #[test]
+    fn exported_finder_removes_options() {
+        let mut ff = crate::FuzzyFinder::default();
+        ff.push_options(["hello", "friend"]);
+        ff.remove_option("hello");
+        assert_eq!(ff.len(), 1);
+        assert!(!ff.matches.contains_key("hello"));
+    }
+
+    #[test]
+    fn edit_filter() {
+        let mut ff = FuzzyFinder::default().with_options(["foo bar", "baz"]);
+        ff.append_to_filter("ba");
+        assert_eq!(ff.matches.len(), 2);
+        ff.append_to_filter("r");
+        assert!(ff.matches["baz"].score.is_none());
+        ff.pop_filter_char();
+        assert_eq!(ff.filter(), "ba");
+        assert!(ff.matches["baz"].score.is_some());
+        ff.pop_filter_word();
+        assert_eq!(ff.filter(), "");
+        ff.pop_filter_word();
+        ff.pop_filter_char();
+        assert_eq!(ff.filter(), "");
+    }
+
+    #[test]
+    fn ranked_entries() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "cde", "xyz"]);
+        ff.set_filter("zzz");
+        assert!(ff.ranked().is_empty());
+        ff.set_filter("c");
+        let ranked = ff.ranked();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].value, "cde");
+        assert_eq!(ranked[0].indices, vec![0]);
+        assert!(ranked.iter().all(|entry| entry.value != "xyz"));
+    }
+
+    #[test]
+    fn multiple_fields() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_fields(["Ada Lovelace", "ada@x.com"]);
+        ff.push_option_fields(["Grace Hopper", "grace@y.com"]);
+        ff.set_filter("hopper");
+        let ranked = ff.ranked();
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].field, 0);
+        ff.set_filter("y.com");
+        let ranked = ff.ranked();
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].value, "Grace Hopper");
+        assert_eq!(ranked[0].field, 1);
+        assert_eq!(ranked[0].indices, vec![6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn field_weights_boost_ranking() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_fields(["grace", "ada@x.com"]);
+        ff.push_option_fields(["ada", "grace@y.com"]);
+        ff.set_filter("grace");
+        ff.set_field_weights([0, 1000]);
+        assert_eq!(ff.selection().unwrap().value, "ada");
+        ff.set_field_weights([1000, 0]);
+        assert_eq!(ff.selection().unwrap().value, "grace");
+    }
+
+    #[test]
+    fn results_in_display_order() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "xyz"]);
+        let results: Vec<_> = ff.results().collect();
+        assert_eq!(
+            results,
+            vec![("abc", Some(0)), ("bcd", Some(0)), ("xyz", Some(0))]
+        );
+        ff.set_filter("xy");
+        let results: Vec<_> = ff.results().collect();
+        assert_eq!(results[0].0, "xyz");
+        assert!(results[0].1.is_some());
+        assert!(results[1..].iter().all(|(_, score)| score.is_none()));
+    }
+
+    #[test]
+    fn pinned_options_rank_first() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "xbc", "create"]);
+        ff.pin_option("create").pin_option("xbc");
+        let order: Vec<_> = ff.matches.keys().map(ToString::to_string).collect();
+        assert_eq!(order, vec!["xbc", "create", "abc"]);
+        ff.set_filter("ab");
+        assert_eq!(ff.selection().unwrap().value, "abc");
+        assert_eq!(ff.ranked().len(), 1);
+        ff.set_filter("c");
+        let ranked: Vec<_> = ff.ranked().into_iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[2], "abc");
+        ff.unpin_option("xbc").unpin_option("create");
+        assert!(!ff.is_pinned("create"));
+    }
+
+    #[test]
+    fn filter_whitespace_is_trimmed() {
+        let mut ff = FuzzyFinder::default().with_options(["foo", "bar"]);
+        ff.set_filter("  foo  ");
+        assert_eq!(ff.filter(), "  foo  ");
+        assert_eq!(ff.ranked().len(), 1);
+        assert!(ff.selection().unwrap().is_exact());
+        ff.set_filter("   ");
+        assert_eq!(ff.ranked().len(), 2);
+        assert!(ff
+            .matches
+            .values()
+            .all(|option| option.score.as_ref().unwrap().score == 0));
+        ff.set_trim_filter(false).set_filter("  foo  ");
+        assert!(!ff.has_matches());
+    }
+
+    #[test]
+    fn changes_are_reported() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "abd"]);
+        assert_eq!(ff.set_filter("ab"), Change::Results);
+        assert_eq!(ff.set_filter("ab"), Change::Unchanged);
+        ff.select_next();
+        assert_eq!(ff.set_filter("ab "), Change::Selection);
+        assert_eq!(ff.set_filter("ab  "), Change::Unchanged);
+        assert_eq!(ff.set_filter("abd"), Change::Results);
+        assert_eq!(ff.set_filter("abdx"), Change::Results);
+        assert_eq!(ff.set_filter("abdxy"), Change::Unchanged);
+        assert_eq!(ff.push_option("xyz"), Change::Unchanged);
+        assert_eq!(ff.clear_filter(), Change::Results);
+        assert_eq!(ff.push_option("abc"), Change::Unchanged);
+    }
+
+    #[test]
+    fn long_options_are_truncated() {
+        let mut ff = FuzzyFinder::default().with_options(["short", "a rather long option"]);
+        ff.set_max_option_len(8);
+        ff.set_filter("option");
+        assert!(!ff.has_matches());
+        ff.set_filter("long");
+        assert!(!ff.has_matches());
+        ff.set_filter("a ra");
+        let selection = ff.selection().unwrap();
+        assert_eq!(selection.value, "a rathe…");
+        assert_eq!(selection.original, "a rather long option");
+        ff.set_filter("short");
+        let selection = ff.selection().unwrap();
+        assert_eq!(selection.value, selection.original);
+        assert_eq!(truncate_option("abc", Some(3)), None);
+        assert_eq!(truncate_option("abcd", Some(3)).as_deref(), Some("ab…"));
+    }
+
+    #[test]
+    fn score_stats() {
+        let mut ff = FuzzyFinder::default();
+        assert!(ff.score_stats().is_none());
+        ff.push_options(["abc", "bcd", "cde"]);
+        let stats = ff.score_stats().unwrap();
+        assert_eq!((stats.max, stats.min, stats.count), (0, 0, 3));
+        ff.set_filter("c");
+        let scores: Vec<_> = ff.results().filter_map(|(_, score)| score).collect();
+        let stats = ff.score_stats().unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.max, *scores.iter().max().unwrap());
+        assert_eq!(stats.min, *scores.iter().min().unwrap());
+        ff.remove_options(["abc", "bcd"]);
+        assert_eq!(ff.score_stats().unwrap().count, 1);
+        ff.remove_option("cde");
+        assert!(ff.score_stats().is_none());
+    }
+
+    #[test]
+    fn normalized_matches_highlight_original() {
+        let mut ff = FuzzyFinder::default().with_options(["résumé", "re\u{301}sume"]);
+        ff.set_normalizer(Normalizer::StripDiacritics);
+        ff.set_filter("resume");
+        let ranked = ff.ranked();
+        assert_eq!(ranked.len(), 2);
+        let composed = ranked.iter().find(|entry| entry.value == "résumé").unwrap();
+        assert_eq!(composed.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert!(composed.is_exact());
+        let decomposed = ranked.iter().find(|entry| entry.value != "résumé").unwrap();
+        assert_eq!(decomposed.indices, vec![0, 1, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn expanded_normalization_highlights_source_char() {
+        fn ligatures(text: &str) -> Cow<'_, str> {
+            Cow::Owned(text.replace('æ', "ae"))
+        }
+        let mut ff = FuzzyFinder::default().with_options(["encyclopædia"]);
+        ff.set_normalizer(Normalizer::Custom(ligatures));
+        ff.set_filter("paed");
+        assert_eq!(ff.selection().unwrap().indices, vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn suffix_bonus_boosts_matches_at_end() {
+        let mut ff = FuzzyFinder::default().with_options(["rs-lib.txt", "x.rs"]);
+        ff.set_filter("rs");
+        let unboosted = ff
+            .ranked()
+            .iter()
+            .find(|e| e.value == "x.rs")
+            .unwrap()
+            .score;
+        ff.set_suffix_bonus(1_000);
+        let ranked = ff.ranked();
+        assert_eq!(ranked[0].value, "x.rs");
+        assert_eq!(ranked[0].score, unboosted + 1_000);
+        let other = ranked.iter().find(|e| e.value == "rs-lib.txt").unwrap();
+        assert!(other.score < 1_000);
+    }
+
+    #[test]
+    fn scores_on_dedicated_pool() {
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(1)
+                .build()
+                .unwrap(),
+        );
+        let mut ff = FuzzyFinder::default();
+        ff.set_thread_pool(Arc::clone(&pool));
+        let metrics = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&metrics);
+        ff.set_metrics_sink(move |_| {
+            sink.lock()
+                .unwrap()
+                .push(rayon::current_thread_index().is_some());
+        });
+        ff.push_options(["abc", "bcd", "cde"]);
+        ff.set_filter("cd");
+        assert_eq!(ff.matched_len(), 2);
+        // rescores ran within the pool rather than on the calling thread.
+        assert!(metrics.lock().unwrap().iter().all(|&pooled| pooled));
+        assert!(rayon::current_thread_index().is_none());
+    }
+
+    #[test]
+    fn entry_display_brackets_matched_value() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_fields(["Ada", "ada@mail.com"]);
+        ff.push_option("mallard");
+        ff.set_filter("mal");
+        let shown: Vec<_> = ff
+            .ranked()
+            .iter()
+            .map(|entry| entry.to_string().split(" (").next().unwrap().to_string())
+            .collect();
+        assert!(shown.contains(&"[mal]lard".to_string()));
+        assert!(shown.contains(&"Ada".to_string()));
+    }
+
+    #[test]
+    fn matcher_options_apply_to_skim_only() {
+        let mut ff = FuzzyFinder::default()
+            .with_matcher_options(SkimMatcherV2::ignore_case)
+            .with_options(["Foo", "foo"])
+            .with_filter("Foo");
+        assert_eq!(ff.matched_len(), 2);
+        ff.set_algorithm(Algorithm::Exact);
+        assert_eq!(ff.matched_len(), 1);
+    }
+
+    #[test]
+    fn optional_separators_match_either_style() {
+        let mut ff = FuzzyFinder::default().with_options(["src\\main.rs", "docs"]);
+        ff.set_filter("src/ma");
+        assert!(!ff.has_matches());
+        ff.set_optional_separators(['/', '\\']);
+        assert_eq!(ff.matched_len(), 1);
+        assert_eq!(ff.selection().unwrap().indices, vec![0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn custom_normalizer() {
+        fn dashes(text: &str) -> Cow<'_, str> {
+            Cow::Owned(text.replace('_', "-"))
+        }
+        let mut ff = FuzzyFinder::default().with_options(["snake_case"]);
+        ff.set_normalizer(Normalizer::Custom(dashes));
+        ff.set_filter("e-c");
+        assert_eq!(ff.selection().unwrap().indices, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn batch_defers_rescoring() {
+        let mut ff = FuzzyFinder::default().with_options(["abc"]);
+        let change = ff.batch(|ff| {
+            ff.push_option("bcd");
+            ff.set_filter("b");
+            assert!(ff.matches["bcd"].score.is_none());
+            assert_eq!(ff.matches["abc"].score.as_ref().unwrap().score, 0);
+            ff.batch(|ff| {
+                ff.clear_filter();
+            });
+            assert!(ff.matches["bcd"].score.is_none());
+            ff.set_filter("cd");
+        });
+        assert_eq!(change, Change::Results);
+        assert!(ff.matches["abc"].score.is_none());
+        assert_eq!(ff.selection().unwrap().value, "bcd");
+        assert_eq!(ff.batch(|_| {}), Change::Unchanged);
+    }
+
+    #[test]
+    fn begin_and_end_batch() {
+        let mut ff = FuzzyFinder::default().with_filter("b");
+        ff.end_batch();
+        ff.begin_batch().begin_batch();
+        ff.push_option("abc");
+        ff.end_batch();
+        assert!(ff.matches["abc"].score.is_none());
+        ff.end_batch();
+        assert!(ff.matches["abc"].score.is_some());
+    }
+
+    #[test]
+    fn update_rescores_within_batch() {
+        let mut ff = FuzzyFinder::default().with_filter("c");
+        assert!(!ff.needs_update());
+        assert_eq!(ff.update(), Change::Unchanged);
+        ff.begin_batch();
+        ff.push_options(["abc", "bcd"]);
+        assert!(ff.needs_update());
+        assert!(!ff.has_matches());
+        assert_eq!(ff.update(), Change::Results);
+        assert!(!ff.needs_update());
+        assert_eq!(ff.ranked().len(), 2);
+        ff.push_option("cde");
+        assert!(ff.needs_update());
+        assert!(ff.matches["cde"].score.is_none());
+        ff.end_batch();
+        assert!(!ff.needs_update());
+        assert_eq!(ff.ranked().len(), 3);
+    }
+
+    #[test]
+    fn entries_have_match_quality() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "xaxbxc"]);
+        ff.set_filter("abc");
+        let ranked = ff.ranked();
+        let quality = |value| {
+            ranked
+                .iter()
+                .find(|entry| entry.value == value)
+                .unwrap()
+                .quality
+        };
+        assert!((quality("abc") - 1.0).abs() < f64::EPSILON);
+        assert!((quality("xaxbxc") - 0.3).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn sequential_sort_ranks_the_same() {
+        let options = ["abc", "bcd", "cde", "xyz", "bc"];
+        let mut parallel = FuzzyFinder::default().with_options(options);
+        let mut sequential = FuzzyFinder::default();
+        sequential.set_sort_parallel(false).push_options(options);
+        parallel.set_filter("bc");
+        sequential.set_filter("bc");
+        assert!(parallel.results().eq(sequential.results()));
+    }
+
+    #[test]
+    fn select_next_where_skips_to_match() {
+        let mut ff = FuzzyFinder::default().with_options(["a1", "b1", "a2", "b2"]);
+        ff.select_next_where(|entry| entry.value.starts_with('b'));
+        assert_eq!(ff.selection().unwrap().value, "b1");
+        ff.select_next_where(|entry| entry.value.starts_with('b'));
+        assert_eq!(ff.selection().unwrap().value, "b2");
+        ff.select_next_where(|entry| entry.value.starts_with('b'));
+        assert_eq!(ff.selection().unwrap().value, "b2");
+        ff.set_filter("1");
+        ff.select_next_where(|entry| entry.value == "a2");
+        assert_eq!(ff.selection().unwrap().value, "a1");
+    }
+
+    #[test]
+    fn used_flag_survives_refiltering() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+        ff.set_used("bcd", true);
+        ff.set_filter("zzz");
+        ff.set_filter("b");
+        assert!(ff.is_used("bcd"));
+        assert!(!ff.is_used("abc"));
+        assert!(ff
+            .ranked()
+            .iter()
+            .any(|entry| entry.value == "bcd" && entry.used));
+    }
+
+    #[test]
+    fn duplicate_options_are_reported() {
+        let mut ff = FuzzyFinder::default().with_options(["abc"]);
+        assert_eq!(
+            ff.try_set_options(["a", "b", "a", "b", "a"]).err(),
+            Some(OptionsError::Duplicates {
+                count: 3,
+                first: "a".into()
+            })
+        );
+        assert_eq!(ff.results().collect::<Vec<_>>(), vec![("abc", Some(0))]);
+        assert!(ff.try_set_options(["a", "b"]).is_ok());
+        assert_eq!(ff.len(), 2);
+    }
+
+    #[test]
+    fn search_key_is_matched_in_place_of_option() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_with_search_key("<b>", "bold");
+        ff.push_option("old");
+        ff.set_filter("bold");
+        assert_eq!(ff.results().collect::<Vec<_>>()[1], ("old", None));
+        assert_eq!(ff.selection().unwrap().value, "<b>");
+        ff.set_filter("<");
+        assert!(!ff.has_matches());
+    }
+
+    #[test]
+    fn short_tokens_are_ignored() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd", "xyz"]);
+        ff.set_filter("x bc");
+        assert_eq!(ff.ranked().len(), 0);
+        ff.set_min_token_len(2);
+        let ranked = ff.ranked();
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.iter().all(|entry| entry.value.contains("bc")));
+        ff.set_filter("x y");
+        assert_eq!(ff.ranked().len(), 3);
+        ff.set_min_token_len(0);
+        assert!(!ff.has_matches());
+    }
+
+    #[test]
+    fn rescore_only_given_options() {
+        let mut ff = FuzzyFinder::default()
+            .with_filter("x")
+            .with_options(["ab", "cd", "ef"]);
+        for option in ff.matches.values_mut() {
+            option.fields = vec!["x".into()];
+        }
+        assert_eq!(ff.rescore_options(["cd", "zz"]), Change::Results);
+        let results: Vec<_> = ff.results().collect();
+        assert_eq!(results[0].0, "cd");
+        assert!(results[0].1.is_some());
+        assert_eq!(results[1].1, None);
+        assert_eq!(results[2].1, None);
+    }
+
+    #[test]
+    fn commit_owns_selection() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+        ff.set_max_option_len(2);
+        ff.select_next();
+        let committed = ff.commit();
+        ff.set_options(["xyz"]);
+        assert_eq!(committed, Some((1, "bcd".to_string())));
+        ff.set_filter("a");
+        assert_eq!(ff.commit(), None);
+    }
+
+    #[test]
+    fn keyed_options_keep_scores() {
+        let mut ff = FuzzyFinder::default()
+            .with_filter("b")
+            .with_options(["ab", "bc", "cd"]);
+        ff.matches
+            .get_mut("bc")
+            .unwrap()
+            .score
+            .as_mut()
+            .unwrap()
+            .score = 1000;
+        assert_eq!(ff.set_options_keyed(["bc", "cd", "bd"]), Change::Results);
+        let results: Vec<_> = ff.results().collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], ("bc", Some(1000)));
+        assert_eq!(results[1].0, "bd");
+        assert_eq!(results[2], ("cd", None));
+    }
+
+    #[test]
+    fn indices_stay_within_truncated_option() {
+        let long = format!("{}z{}", "a".repeat(200), "a".repeat(49));
+        let mut ff = FuzzyFinder::default();
+        ff.set_max_option_len(100);
+        ff.push_option(long.clone());
+        ff.set_filter("z");
+        assert!(!ff.has_matches());
+        ff.clear_filter();
+        ff.matches.get_mut(long.as_str()).unwrap().score = Some(FuzzyScore {
+            score: 1,
+            indices: vec![98, 99, 200],
+            field: 0,
+            quality: 0.0,
+        });
+        assert_eq!(ff.selection().unwrap().indices, vec![98, 99]);
+    }
+
+    #[test]
+    fn all_fields_must_match() {
+        let mut ff = FuzzyFinder::default();
+        ff.set_field_match(FieldMatch::All);
+        ff.push_option_fields(["abc", "xbx"]);
+        ff.push_option_fields(["abd", "xyz"]);
+        ff.set_filter("b");
+        let ranked = ff.ranked();
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].value, "abc");
+        ff.set_field_match(FieldMatch::Any);
+        assert_eq!(ff.ranked().len(), 2);
+    }
+
+    #[test]
+    fn algorithm_changes_rescore() {
+        let mut ff = FuzzyFinder::default()
+            .with_filter("abc")
+            .with_options(["a_b_c", "xabcx"]);
+        let scores = |ff: &FuzzyFinder| -> Vec<Option<i64>> {
+            ff.results().map(|(_, score)| score).collect()
+        };
+        let skim = scores(&ff);
+        assert!(skim.iter().all(Option::is_some));
+        ff.set_algorithm(Algorithm::Exact);
+        assert_ne!(scores(&ff), skim);
+    }
+
+    #[cfg(feature = "nucleo")]
+    #[test]
+    fn nucleo_algorithm_scores_as_fuzzy_scores() {
+        let mut ff = FuzzyFinder::default()
+            .with_filter("abc")
+            .with_options(["xaxbxcx", "abc", "nope"]);
+        ff.set_algorithm(Algorithm::Nucleo);
+        let ranked: Vec<(&str, Vec<usize>)> = ff
+            .ranked()
+            .into_iter()
+            .map(|entry| (entry.value, entry.indices))
+            .collect();
+        assert_eq!(ranked, [("abc", vec![0, 1, 2]), ("xaxbxcx", vec![1, 3, 5])]);
+    }
+
+    #[test]
+    fn contains_and_score_of() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "xyz"]);
+        assert_eq!(ff.score_of("abc"), Some(0));
+        ff.set_filter("z");
+        assert!(ff.contains("abc"));
+        assert_eq!(ff.score_of("abc"), None);
+        assert_eq!(ff.score_of("xyz"), ff.best_match().map(|entry| entry.score));
+        assert!(!ff.contains("nope"));
+        assert_eq!(ff.score_of("nope"), None);
+    }
+
+    #[test]
+    fn empty_filter_can_show_nothing() {
+        let mut ff = FuzzyFinder::default();
+        ff.set_show_all_on_empty(false);
+        ff.push_options(["abc", "bcd"]);
+        assert!(!ff.has_matches());
+        assert!(ff.selection().is_none());
+        ff.set_filter("c");
+        assert_eq!(ff.ranked().len(), 2);
+        ff.clear_filter();
+        assert!(ff.ranked().is_empty());
+        ff.set_show_all_on_empty(true);
+        assert_eq!(ff.ranked().len(), 2);
+    }
+
+    #[test]
+    fn longer_filter_only_rescores_matches() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "abd", "xyz"]);
+        ff.set_filter("a");
+        // pretend "abd" didn't match, so narrowing skips it.
+        ff.matches.get_mut("abd").unwrap().score = None;
+        ff.set_filter("ab");
+        assert_eq!(ff.score_of("abd"), None);
+        assert!(ff.score_of("abc").is_some());
+        ff.set_filter("b");
+        assert!(ff.score_of("abd").is_some());
+        ff.set_filter("bd");
+        assert_eq!(ff.ranked().len(), 1);
+        assert_eq!(ff.best_match().unwrap().value, "abd");
+    }
+
+    #[test]
+    fn entries_are_ranked() {
+        let mut ff = FuzzyFinder::default().with_options(["a", "b", "c"]);
+        ff.select_next();
+        assert_eq!(ff.selection().unwrap().rank(), 1);
+        let ranks: Vec<_> = ff.ranked().iter().map(FuzzyListEntry::rank).collect();
+        assert_eq!(ranks, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn sort_key_breaks_ties() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_with_sort_key("ab1", 1);
+        ff.push_option_with_sort_key("ab2", 3);
+        ff.push_option_with_sort_key("xab", 2);
+        ff.push_option_with_sort_key("zzz", 9);
+        ff.set_filter("ab");
+        let first: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(first[..2], ["ab2", "ab1"]);
+        assert_eq!(first.len(), 3);
+    }
+
+    #[test]
+    fn matches_are_grouped_by_best_match() {
+        let mut ff = FuzzyFinder::default();
+        ff.push_option_in_group("a1", "Git");
+        ff.push_option_in_group("a2", "File");
+        ff.push_option_in_group("a3", "Git");
+        ff.push_option("a4");
+        ff.set_weight("a2", 10)
+            .set_weight("a1", 5)
+            .set_weight("a4", 3);
+        ff.set_filter("a");
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked, ["a2", "a1", "a3", "a4"]);
+    }
+
+    #[test]
+    fn retain_clamps_selection() {
+        let mut ff = FuzzyFinder::default().with_options(["a", "b", "c"]);
+        ff.select_next().select_next();
+        ff.retain(|option| option != "c");
+        assert_eq!(ff.state.selected(), Some(1));
+        ff.retain(|_| false);
+        assert_eq!(ff.state.selected(), None);
+    }
+
+    #[test]
+    fn selection_follows_reordered_option() {
+        let mut ff = FuzzyFinder::default().with_options(["a", "b", "c"]);
+        ff.select_next();
+        ff.matches.swap_indices(0, 1);
+        assert_eq!(ff.selection().unwrap().value, "b");
+        assert_eq!(ff.commit(), Some((0, "b".to_string())));
+    }
+
+    #[test]
+    fn preserved_input_order_filters_without_ranking() {
+        let mut ff = FuzzyFinder::default().with_options(["xaxbx", "zz", "ab", "axb"]);
+        ff.set_preserve_input_order(true);
+        ff.set_filter("ab");
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked, ["xaxbx", "ab", "axb"]);
+        ff.set_preserve_input_order(false);
+        assert_eq!(ff.selection().unwrap().value, "ab");
+    }
+
+    #[test]
+    fn custom_order_keeps_filtering() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "xaxbxc", "zzz", "ab_c"]);
+        ff.set_sort_by(Box::new(|e1, e2| e2.value.len().cmp(&e1.value.len())));
+        ff.set_filter("abc");
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked, ["xaxbxc", "ab_c", "abc"]);
+    }
+
+    #[test]
+    fn proportional_selection_scales_with_matches() {
+        let options: Vec<String> = (0..10).map(|i| format!("a{i}")).collect();
+        let mut ff = FuzzyFinder::default().with_options(options.iter().map(String::as_str));
+        ff.set_selection_policy(SelectionPolicy::Proportional);
+        ff.push_options(["b0", "b1", "b2"]);
+        for _ in 0..6 {
+            ff.select_next();
+        }
+        // halfway down all 13 options, so halfway down the 10 matching.
+        ff.set_filter("a");
+        assert_eq!(ff.state.selected(), Some(5));
+    }
+
+    #[test]
+    fn kept_selection_falls_back_to_top() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "abd", "xyz"]);
+        ff.set_selection_policy(SelectionPolicy::KeepByValue);
+        ff.select_next().select_next();
+        ff.set_filter("ab");
+        assert_eq!(ff.state.selected(), Some(0));
+    }
+
+    #[test]
+    fn acronyms_rank_first() {
+        let mut ff = FuzzyFinder::default().with_options([
+            "gcd.rs",
+            "Greatest Common Divisor",
+            "getCurrentDirectory",
+        ]);
+        ff.set_filter("gcd");
+        assert_eq!(ff.selection().unwrap().value, "gcd.rs");
+        ff.set_match_mode(MatchMode::Acronym);
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked[2], "gcd.rs");
+        let entry = ff.matches.get_index_of("Greatest Common Divisor").unwrap();
+        assert_eq!(ff.entry(entry).unwrap().indices, vec![0, 9, 16]);
+        assert_eq!(
+            acronym_indices("getCurrentDirectory", "g c d"),
+            Some(vec![0, 3, 10])
+        );
+        assert_eq!(acronym_indices("Greatest Common", "gcd"), None);
+    }
+
+    #[test]
+    fn narrowing_reports_fewer_scored() {
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+        let mut ff = FuzzyFinder::default().with_options(["abc", "abd", "xyz"]);
+        ff.set_metrics_sink(move |metrics: UpdateMetrics| {
+            sink.lock().unwrap().push((metrics.scored, metrics.matched));
+        });
+        ff.set_filter("a");
+        ff.set_filter("ab");
+        ff.set_filter("abc");
+        assert_eq!(*reported.lock().unwrap(), [(3, 2), (2, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn sanitizing_cleans_then_truncates() {
+        assert_eq!(Sanitizer::None.apply("a\tb"), "a\tb");
+        assert_eq!(Sanitizer::ControlChars.apply("a\tb\0\u{200b}c\r"), "a bc");
+        assert_eq!(
+            Sanitizer::Ansi.apply("\x1b]8;;file:///a\x1b\\a\x1b]8;;\x07 \x1b[38;5;1mb\x1b[m\x1b="),
+            "a b"
+        );
+        assert!(matches!(Sanitizer::Ansi.apply("plain"), Cow::Borrowed(_)));
+        let mut ff = FuzzyFinder::default().with_options(["\x1b[1mhello world\x1b[0m"]);
+        ff.set_max_option_len(6).set_sanitizer(Sanitizer::Ansi);
+        assert_eq!(ff.selection().unwrap().value, "hello…");
+        ff.set_filter("hel");
+        assert_eq!(ff.selection().unwrap().indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn ansi_styles_are_parsed() {
+        let (visible, styles) = parse_ansi("\x1b[1;31mab\x1b[22m\tc\x1b[0m\x1b[38;2;1;2;3md");
+        assert_eq!(visible, "ab cd");
+        let red = Style::default().fg(Color::Red);
+        assert_eq!(
+            styles,
+            vec![
+                (0, red.add_modifier(Modifier::BOLD)),
+                (
+                    2,
+                    red.add_modifier(Modifier::BOLD)
+                        .remove_modifier(Modifier::BOLD | Modifier::DIM)
+                ),
+                (4, Style::default().fg(Color::Rgb(1, 2, 3))),
+            ]
+        );
+    }
+
+    #[test]
+    fn extended_tokens_must_all_match() {
+        let mut ff = FuzzyFinder::default().with_options([
+            "src/test_mock.rs",
+            "src/Test/main.rs",
+            "src/test/main.rs",
+            "tests/test.rs",
+            "src/test.toml",
+        ]);
+        ff.set_match_mode(MatchMode::Extended);
+        ff.set_filter("^src 'test .rs$ !mock");
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked, ["src/Test/main.rs", "src/test/main.rs"]);
+        assert_eq!(
+            ff.selection().unwrap().indices,
+            vec![0, 1, 2, 4, 5, 6, 7, 13, 14, 15]
+        );
+        ff.set_filter("'Test");
+        assert_eq!(ff.ranked().len(), 1);
+        assert_eq!(FilterToken::parse("!"), FilterToken::Fuzzy("!"));
+        assert_eq!(FilterToken::parse("$"), FilterToken::Fuzzy("$"));
+    }
+
+    #[test]
+    fn extended_filter_changing_token_kind_rescores() {
+        let mut ff = FuzzyFinder::default().with_options(["apple", "banana"]);
+        ff.set_match_mode(MatchMode::Extended);
+        ff.set_filter("!");
+        assert_eq!(ff.matched_len(), 0);
+        ff.set_filter("!x");
+        assert_eq!(ff.matched_len(), 2);
+        ff.set_filter("!a");
+        ff.set_filter("!an");
+        assert_eq!(ff.matched_len(), 1);
+        ff.set_filter("!an 'ap");
+        assert_eq!(ff.matched_len(), 1);
+    }
+
+    #[test]
+    fn select_value_leaves_selection_if_unmatched() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "abd", "xyz"]);
+        ff.set_filter("ab");
+        assert!(ff.select_value("abd"));
+        assert!(!ff.select_value("xyz"));
+        assert!(!ff.select_value("nope"));
+        assert_eq!(ff.selection().unwrap().value, "abd");
+    }
+
+    #[test]
+    fn lazy_scoring_expands_as_selection_moves() {
+        let options: Vec<String> = (0..10_000).map(|i| format!("a{i}")).collect();
+        let mut ff = FuzzyFinder::default().with_options(options.iter().map(String::as_str));
+        ff.set_lazy_scoring(5);
+        ff.set_filter("a");
+        assert_eq!(ff.matched_len(), LAZY_CHUNK);
+        ff.select(LAZY_CHUNK - 1);
+        assert_eq!(ff.matched_len(), 2 * LAZY_CHUNK);
+        assert_eq!(ff.state.selected(), Some(LAZY_CHUNK - 1));
+        ff.set_filter("a1");
+        assert!(ff.has_matches());
+        ff.select(9_999);
+        let expected = options.iter().filter(|o| o[1..].contains('1')).count();
+        assert_eq!(ff.matched_len(), expected);
+    }
+
+    #[test]
+    fn lazy_scoring_scores_a_chunk_per_expansion() {
+        let options: Vec<String> = (0..4 * LAZY_CHUNK).map(|i| format!("a{i}")).collect();
+        let mut ff = FuzzyFinder::default()
+            .with_options(options.iter().map(String::as_str))
+            .with_filter("a");
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+        ff.set_metrics_sink(move |metrics: UpdateMetrics| {
+            sink.lock().unwrap().push(metrics.scored);
+        });
+        ff.set_lazy_scoring(5);
+        ff.set_filter("a1");
+        for _ in 0..3 {
+            ff.select(ff.matched_len() - 1);
+        }
+        ff.push_option("a1x");
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 6);
+        assert!(reported.iter().all(|&scored| scored <= LAZY_CHUNK));
+        // merged chunk by chunk, yet ranked as a full sort would.
+        let ranked: Vec<&FuzzyOption> = ff.matches.values().take(ff.matched_len()).collect();
+        assert!(ranked
+            .windows(2)
+            .all(|pair| pair[0].cmp_rank(pair[1]) == Ordering::Less));
+    }
+
+    #[test]
+    fn push_variants_report_changes() {
+        let mut ff = FuzzyFinder::default().with_filter("git");
+        assert_eq!(ff.push_option_in_group("git push", "Git"), Change::Results);
+        assert_eq!(ff.push_option_weighted("cargo build", 5), Change::Unchanged);
+        assert_eq!(
+            ff.push_option_fields(["ada", "ada@git.io"]),
+            Change::Results
+        );
+        assert_eq!(ff.push_option_fields(Vec::<&str>::new()), Change::Unchanged);
+    }
+
+    #[test]
+    fn reordering_without_rescoring_reports_results() {
+        let mut ff = FuzzyFinder::default().with_options(["ab", "axb", "zzz"]);
+        ff.set_filter("ab");
+        assert_eq!(ff.push_option_weighted("axb", 0), Change::Unchanged);
+        assert_eq!(ff.push_option_weighted("axb", 100), Change::Results);
+        assert_eq!(ff.ranked()[0].value, "axb");
+        assert_eq!(ff.batch(|ff| ff.remove_option("zzz")), Change::Unchanged);
+        assert_eq!(ff.batch(|ff| ff.remove_option("ab")), Change::Results);
+    }
+
+    #[test]
+    fn clear_selection_keeps_filter_and_options() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+        ff.set_filter("b");
+        ff.select_next().clear_selection();
+        assert!(ff.selection().is_none());
+        assert!(ff.commit().is_none());
+        assert_eq!(ff.filter(), "b");
+        assert_eq!(ff.matched_len(), 2);
+    }
+
+    #[test]
+    fn options_generation_counts_option_changes() {
+        let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+        let generation = ff.options_generation();
+        ff.push_option("abc");
+        ff.set_filter("c");
+        ff.select_next();
+        ff.retain(|_| true);
+        ff.remove_option("missing");
+        assert_eq!(ff.options_generation(), generation);
+        ff.push_option("cde");
+        assert_eq!(ff.options_generation(), generation + 1);
+        ff.set_options(["xyz"]);
+        assert!(ff.options_generation() > generation + 1);
+    }
+
+    #[test]
+    fn swap_removal_defers_sorting_and_keeps_selection() {
+        let mut ff = FuzzyFinder::default().with_options(["ab", "axb", "axxb", "zzz"]);
+        ff.set_selection_policy(SelectionPolicy::ResetToTop);
+        ff.set_filter("ab");
+        ff.select_next().select_next();
+        assert_eq!(ff.selection().unwrap().value, "axxb");
+        ff.swap_remove_options(["ab", "missing"]);
+        // no re-sort: the last match moved into the gap, ahead of the non-match.
+        let order: Vec<_> = ff.matches.keys().map(AsRef::as_ref).collect();
+        assert_eq!(order, ["axxb", "axb", "zzz"]);
+        assert_eq!(ff.matched_len(), 2);
+        assert_eq!(ff.selection().unwrap().value, "axxb");
+        assert!(ff.needs_update());
+        ff.update();
+        let ranked: Vec<_> = ff.ranked().iter().map(|entry| entry.value).collect();
+        assert_eq!(ranked, ["axb", "axxb"]);
     }
 
     #[test]
@@ -0,0 +1,217 @@
+use crate::{Change, FuzzyFinder};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+impl FuzzyFinder<'_> {
+    /// Byte offset of the editing cursor in the filter.
+    fn cursor_offset(&self) -> usize {
+        let filter = self.filter();
+        self.cursor
+            .and_then(|cursor| filter.grapheme_indices(true).nth(cursor))
+            .map_or(filter.len(), |(offset, _)| offset)
+    }
+
+    /// Replaces the filter with `before` and `after` joined, leaving the cursor
+    /// between them unless it was at the end.
+    fn edit_filter(&mut self, before: &str, after: &str) -> Change {
+        if self.cursor.is_some() {
+            self.cursor = Some(before.graphemes(true).count());
+        }
+        let filter = format!("{before}{after}");
+        self.set_filter(filter)
+    }
+
+    /// Display column of the editing cursor within the filter, e.g. to place the
+    /// terminal cursor after rendering the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("日本");
+    /// assert_eq!(ff.cursor_column(), 4);
+    /// ff.move_cursor_left();
+    /// assert_eq!(ff.cursor_column(), 2);
+    /// ```
+    pub fn cursor_column(&self) -> usize {
+        self.filter()[..self.cursor_offset()].width()
+    }
+
+    /// Inserts `text` into the filter at the cursor, moving the cursor past it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("ac");
+    /// ff.move_cursor_left();
+    /// ff.insert_at_cursor("b");
+    /// assert_eq!(ff.filter(), "abc");
+    /// ```
+    pub fn insert_at_cursor(&mut self, text: &str) -> Change {
+        let offset = self.cursor_offset();
+        let filter = self.filter();
+        let before = format!("{}{text}", &filter[..offset]);
+        let after = filter[offset..].to_string();
+        self.edit_filter(&before, &after)
+    }
+
+    /// Deletes the character before the cursor, like Backspace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("abc");
+    /// ff.move_cursor_left();
+    /// ff.delete_before_cursor();
+    /// assert_eq!(ff.filter(), "ac");
+    /// ```
+    pub fn delete_before_cursor(&mut self) -> Change {
+        let offset = self.cursor_offset();
+        let filter = self.filter();
+        let Some((start, _)) = filter[..offset].grapheme_indices(true).next_back() else {
+            return Change::Unchanged;
+        };
+        let (before, after) = (filter[..start].to_string(), filter[offset..].to_string());
+        self.edit_filter(&before, &after)
+    }
+
+    /// Deletes the character under the cursor, like Delete.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("abc");
+    /// ff.move_cursor_to_start();
+    /// ff.delete_at_cursor();
+    /// assert_eq!(ff.filter(), "bc");
+    /// ```
+    pub fn delete_at_cursor(&mut self) -> Change {
+        let offset = self.cursor_offset();
+        let filter = self.filter();
+        let Some(grapheme) = filter[offset..].graphemes(true).next() else {
+            return Change::Unchanged;
+        };
+        let before = filter[..offset].to_string();
+        let after = filter[offset + grapheme.len()..].to_string();
+        self.edit_filter(&before, &after)
+    }
+
+    /// Deletes the whitespace separated word before the cursor, like Ctrl-W.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("foo bar baz");
+    /// ff.move_cursor_left().move_cursor_left().move_cursor_left().move_cursor_left();
+    /// ff.delete_word_before_cursor();
+    /// assert_eq!(ff.filter(), "foo  baz");
+    /// ```
+    pub fn delete_word_before_cursor(&mut self) -> Change {
+        let offset = self.cursor_offset();
+        let filter = self.filter();
+        let trimmed = filter[..offset].trim_end();
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let (before, after) = (trimmed[..start].to_string(), filter[offset..].to_string());
+        self.edit_filter(&before, &after)
+    }
+
+    /// Moves the cursor one character left, stopping at the start of the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("ab");
+    /// ff.move_cursor_left();
+    /// assert_eq!(ff.cursor_column(), 1);
+    /// ```
+    pub fn move_cursor_left(&mut self) -> &mut Self {
+        let before = self.filter()[..self.cursor_offset()]
+            .graphemes(true)
+            .count();
+        self.cursor = Some(before.saturating_sub(1));
+        self
+    }
+
+    /// Moves the cursor one character right, stopping at the end of the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyFinder;
+    ///
+    /// let mut ff = FuzzyFinder::default().with_filter("ab");
+    /// ff.move_cursor_to_start().move_cursor_right();
+    /// assert_eq!(ff.cursor_column(), 1);
+    /// ```
+    pub fn move_cursor_right(&mut self) -> &mut Self {
+        if let Some(cursor) = self.cursor {
+            let len = self.filter().graphemes(true).count();
+            self.cursor = (cursor + 1 < len).then_some(cursor + 1);
+        }
+        self
+    }
+
+    /// Moves the cursor to the start of the filter.
+    pub fn move_cursor_to_start(&mut self) -> &mut Self {
+        self.cursor = Some(0);
+        self
+    }
+
+    /// Moves the cursor to the end of the filter, where it follows edits made by
+    /// `set_filter` and friends.
+    pub fn move_cursor_to_end(&mut self) -> &mut Self {
+        self.cursor = None;
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edits_follow_cursor() {
+        let mut ff = FuzzyFinder::default();
+        ff.insert_at_cursor("ad");
+        ff.move_cursor_left();
+        ff.insert_at_cursor("bc");
+        assert_eq!(ff.filter(), "abcd");
+        assert_eq!(ff.cursor_column(), 3);
+        ff.delete_at_cursor();
+        assert_eq!(ff.filter(), "abc");
+        assert_eq!(ff.delete_at_cursor(), Change::Unchanged);
+        ff.move_cursor_to_start();
+        assert_eq!(ff.delete_before_cursor(), Change::Unchanged);
+        ff.move_cursor_right().move_cursor_right();
+        ff.delete_before_cursor();
+        assert_eq!(ff.filter(), "ac");
+        assert_eq!(ff.cursor_column(), 1);
+    }
+
+    #[test]
+    fn cursor_is_clamped_to_filter() {
+        let mut ff = FuzzyFinder::default().with_filter("abc");
+        ff.move_cursor_left();
+        ff.set_filter("a");
+        assert_eq!(ff.cursor_column(), 1);
+        ff.move_cursor_right();
+        ff.insert_at_cursor("é");
+        assert_eq!(ff.filter(), "aé");
+        assert_eq!(ff.cursor_column(), 2);
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
+    borrow::Cow,
     ops::{Bound, RangeBounds},
     slice::SliceIndex,
 };
 use thiserror::Error;
 use tracing::error;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, PartialEq)]
 pub enum Style<'a> {
@@ -19,6 +21,8 @@ pub enum MatchHighlightError {
         end: Bound<usize>,
         string: String,
     },
+    #[error("match index {index} out of range for {len} chars")]
+    IndexOutOfRange { index: usize, len: usize },
 }
 
 fn get_substring<R: RangeBounds<usize> + SliceIndex<str> + Clone>(
@@ -37,43 +41,149 @@ fn get_substring<R: RangeBounds<usize> + SliceIndex<str> + Clone>(
     })
 }
 
+/// Merges matched char `indices` into contiguous `(start, end)` char ranges, `end`
+/// exclusive, e.g. for exporting highlights.  Indices needn't be sorted or unique.
+///
+/// # Example
+///
+/// ```
+/// use tuiscope::matched_indices_merged;
+///
+/// assert_eq!(matched_indices_merged(&[5, 1, 2, 2, 3]), vec![(1, 4), (5, 6)]);
+/// assert!(matched_indices_merged(&[]).is_empty());
+/// ```
+pub fn matched_indices_merged(indices: &[usize]) -> Vec<(usize, usize)> {
+    let indices: Cow<[usize]> = if indices.windows(2).all(|pair| pair[0] < pair[1]) {
+        Cow::Borrowed(indices)
+    } else {
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        Cow::Owned(sorted)
+    };
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &index in indices.iter() {
+        match ranges.last_mut() {
+            Some((_, end)) if *end == index => *end += 1,
+            _ => ranges.push((index, index + 1)),
+        }
+    }
+    ranges
+}
+
+/// Splits `string` into matched and unmatched sections given the char `indices` of
+/// matched chars.  Indices needn't be sorted or unique, so needn't come from skim.
+/// Sections always break on grapheme cluster boundaries,
+/// a cluster being matched if any of its chars are.
+/// An index past the last char, e.g. from a mismatched matcher, is an
+/// `IndexOutOfRange` error.
+///
+/// Sections are in logical (memory) order and always concatenate back to `string`.
+/// Reordering right-to-left text for display is left to the terminal, so highlighting
+/// of Arabic or Hebrew options is only as good as its bidi support, and a match
+/// spanning a change of direction may display as disjoint runs.
 pub fn sections_from_stringdices<'a>(
     string: &'a str,
-    indices: &'a [usize],
+    indices: &[usize],
 ) -> Result<Vec<Style<'a>>, MatchHighlightError> {
-    let mut ret = Vec::new();
-    let mut indices = indices.iter().peekable();
-    let mut i: usize = 0;
-    while let Some(m) = indices.next() {
-        if *m > 0 {
-            let sub = get_substring(string, i..*m)?;
-            ret.push(Style::None(sub));
+    let ranges = matched_indices_merged(indices);
+    if let Some(&(_, end)) = ranges.last() {
+        let len = string.chars().count();
+        if end > len {
+            let index = end - 1;
+            error! {"Match index {} out of range for `{}`", index, string};
+            return Err(MatchHighlightError::IndexOutOfRange { index, len });
         }
-        i = *m;
-        let mut j = *m + 1;
-        while let Some(m) = indices.peek() {
-            if *m > &j {
-                break;
+    }
+    let mut ret = Vec::new();
+    let mut ranges = ranges.iter().peekable();
+    let mut char_index: usize = 0;
+    let mut start: usize = 0;
+    let mut matched = false;
+    for (offset, grapheme) in string.grapheme_indices(true) {
+        let next_char_index = char_index + grapheme.chars().count();
+        // skip runs wholly before this cluster, matching it if the next overlaps.
+        while ranges.next_if(|(_, end)| *end <= char_index).is_some() {}
+        let is_match = ranges
+            .peek()
+            .map_or(false, |(start, _)| *start < next_char_index);
+        if is_match != matched {
+            if offset > start {
+                ret.push(section(get_substring(string, start..offset)?, matched));
             }
-            indices.next();
-            j += 1;
+            start = offset;
+            matched = is_match;
         }
-        let sub = get_substring(string, i..j)?;
-        ret.push(Style::Matched(sub));
-        i = j;
+        char_index = next_char_index;
     }
-    if i < string.len() {
-        let sub = get_substring(string, i..)?;
-        ret.push(Style::None(sub));
+    if start < string.len() {
+        ret.push(section(get_substring(string, start..)?, matched));
     }
     Ok(ret)
 }
 
+/// Renders `value` with each run of matched chars, given by their char `indices`,
+/// wrapped in `open` and `close` markers, e.g. `<b>` and `</b>`, or ANSI SGR codes.
+/// Runs follow `sections_from_stringdices`, so never split a grapheme cluster.
+///
+/// # Example
+///
+/// ```
+/// use tuiscope::render_marked;
+///
+/// assert_eq!(render_marked("héllo", &[1, 2, 4], "<b>", "</b>"), "h<b>él</b>l<b>o</b>");
+/// ```
+pub fn render_marked(value: &str, indices: &[usize], open: &str, close: &str) -> String {
+    let Ok(sections) = sections_from_stringdices(value, indices) else {
+        return value.to_string();
+    };
+    let mut marked = String::with_capacity(value.len());
+    for section in sections {
+        match section {
+            Style::None(sub) => marked.push_str(sub),
+            Style::Matched(sub) => {
+                marked.push_str(open);
+                marked.push_str(sub);
+                marked.push_str(close);
+            }
+        }
+    }
+    marked
+}
+
+fn section(sub: &str, matched: bool) -> Style<'_> {
+    if matched {
+        Style::Matched(sub)
+    } else {
+        Style::None(sub)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use anyhow::Result;
 
+    #[test]
+    fn marked_runs() {
+        assert_eq!(render_marked("abc", &[], "[", "]"), "abc");
+        assert_eq!(render_marked("abc", &[0, 1, 2], "[", "]"), "[abc]");
+        assert_eq!(
+            render_marked("e\u{301}xy", &[1, 3], "\x1b[1m", "\x1b[0m"),
+            "\x1b[1me\u{301}\x1b[0mx\x1b[1my\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn merged_ranges() {
+        assert!(matched_indices_merged(&[]).is_empty());
+        assert_eq!(matched_indices_merged(&[0, 1, 2]), vec![(0, 3)]);
+        assert_eq!(
+            matched_indices_merged(&[4, 2, 0]),
+            vec![(0, 1), (2, 3), (4, 5)]
+        );
+    }
+
     #[test]
     fn no_highlight() -> Result<()> {
         assert_eq!(
@@ -83,6 +193,15 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn unsorted_duplicate_indices() -> Result<()> {
+        assert_eq!(
+            sections_from_stringdices("abcde", &[2, 0, 0, 1])?,
+            vec![Style::Matched("abc"), Style::None("de")]
+        );
+        Ok(())
+    }
+
     #[test]
     fn highlight_one_char_at_start() -> Result<()> {
         assert_eq!(
@@ -138,7 +257,6 @@ mod test {
 
     // These 2 bugs occurred when starting a search with 's'
     // Note the first s in both cases is after a special character
-    #[ignore]
     #[test]
     fn found_bug_1() -> Result<()> {
         // This used to error
@@ -147,7 +265,6 @@ mod test {
     }
 
     // It looks like unicode is the cause?
-    #[ignore]
     #[test]
     fn found_bug_2() -> Result<()> {
         // This used to error
@@ -155,6 +272,68 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn highlight_whole_zwj_sequence() -> Result<()> {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let string = format!("a{family}b");
+        for index in 1..=5 {
+            assert_eq!(
+                sections_from_stringdices(&string, &[index])?,
+                vec![Style::None("a"), Style::Matched(family), Style::None("b")]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn highlight_whole_combining_sequence() -> Result<()> {
+        assert_eq!(
+            sections_from_stringdices("cafe\u{301}s", &[3])?,
+            vec![
+                Style::None("caf"),
+                Style::Matched("e\u{301}"),
+                Style::None("s")
+            ]
+        );
+        assert_eq!(
+            sections_from_stringdices("cafe\u{301}s", &[4, 5])?,
+            vec![Style::None("caf"), Style::Matched("e\u{301}s")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rtl_sections_concatenate_to_input() -> Result<()> {
+        let string = "שלום עולם";
+        let sections = sections_from_stringdices(string, &[0, 1, 5])?;
+        assert_eq!(
+            sections,
+            vec![
+                Style::Matched("של"),
+                Style::None("ום "),
+                Style::Matched("ע"),
+                Style::None("ולם")
+            ]
+        );
+        let joined: String = sections
+            .iter()
+            .map(|section| match section {
+                Style::None(sub) | Style::Matched(sub) => *sub,
+            })
+            .collect();
+        assert_eq!(joined, string);
+        Ok(())
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        assert!(matches!(
+            sections_from_stringdices("héllo", &[1, 5]),
+            Err(MatchHighlightError::IndexOutOfRange { index: 5, len: 5 })
+        ));
+        assert!(sections_from_stringdices("héllo", &[4]).is_ok());
+    }
+
     #[test]
     fn periods_are_ok() -> Result<()> {
         sections_from_stringdices("ABC.DEF.GHI", &[0, 4])?;
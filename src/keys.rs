@@ -0,0 +1,146 @@
+use crate::{Change, FuzzyFinder};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+impl FuzzyFinder<'_> {
+    /// Applies a key event, returning what that changed.  Presses and repeats are
+    /// handled alike, releases ignored:
+    ///
+    /// - Up/Down, or Ctrl-P/Ctrl-N, move the selection by one entry.
+    /// - PgUp/PgDn move it by the rows shown when last rendered.
+    /// - Home/End select the first/last match.
+    /// - Printable characters are appended to the filter.
+    /// - Backspace deletes the last character, Ctrl-W the last word, and Ctrl-U
+    ///   clears the filter.
+    ///
+    /// With the `editor` feature, characters are inserted and deleted at the editing
+    /// cursor instead of the end of the filter.
+    ///
+    /// Other keys are left to the caller.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    /// use tuiscope::{Change, FuzzyFinder};
+    ///
+    /// let mut ff = FuzzyFinder::default().with_options(["abc", "bcd"]);
+    /// let key = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE);
+    /// assert_eq!(ff.handle_key_event(&key), Change::Results);
+    /// assert_eq!(ff.filter(), "d");
+    /// ```
+    pub fn handle_key_event(&mut self, key: &KeyEvent) -> Change {
+        if key.kind == KeyEventKind::Release {
+            return Change::Unchanged;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Up => self.tracking_changes(|ff| {
+                ff.select_prev();
+            }),
+            KeyCode::Char('p') if ctrl => self.tracking_changes(|ff| {
+                ff.select_prev();
+            }),
+            KeyCode::Down => self.tracking_changes(|ff| {
+                ff.select_next();
+            }),
+            KeyCode::Char('n') if ctrl => self.tracking_changes(|ff| {
+                ff.select_next();
+            }),
+            KeyCode::PageUp => self.tracking_changes(|ff| {
+                let current = ff.selected_index().unwrap_or_default();
+                ff.select(current.saturating_sub(ff.page_len()));
+            }),
+            KeyCode::PageDown => self.tracking_changes(|ff| {
+                let current = ff.selected_index().unwrap_or_default();
+                let last = ff.matched_len().saturating_sub(1);
+                ff.select((current + ff.page_len()).min(last));
+            }),
+            KeyCode::Home => self.tracking_changes(|ff| {
+                ff.select(0);
+            }),
+            KeyCode::End => self.tracking_changes(|ff| {
+                ff.select(ff.matched_len().saturating_sub(1));
+            }),
+            #[cfg(feature = "editor")]
+            KeyCode::Backspace => self.delete_before_cursor(),
+            #[cfg(not(feature = "editor"))]
+            KeyCode::Backspace => self.pop_filter_char(),
+            #[cfg(feature = "editor")]
+            KeyCode::Char('w') if ctrl => self.delete_word_before_cursor(),
+            #[cfg(not(feature = "editor"))]
+            KeyCode::Char('w') if ctrl => self.pop_filter_word(),
+            KeyCode::Char('u') if ctrl => self.clear_filter(),
+            #[cfg(feature = "editor")]
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_at_cursor(c.encode_utf8(&mut [0; 4])),
+            #[cfg(not(feature = "editor"))]
+            KeyCode::Char(c) if !ctrl && !alt => self.append_to_filter(c.encode_utf8(&mut [0; 4])),
+            _ => Change::Unchanged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn navigation_keys_move_selection() {
+        let options: Vec<_> = (0..10).map(|i| format!("{i}")).collect();
+        let mut ff = FuzzyFinder::default();
+        ff.push_options(&options);
+        ff.set_page_len(4);
+        assert_eq!(
+            ff.handle_key_event(&press(KeyCode::PageDown)),
+            Change::Selection
+        );
+        assert_eq!(ff.state.selected(), Some(4));
+        ff.handle_key_event(&press(KeyCode::Up));
+        assert_eq!(ff.state.selected(), Some(3));
+        ff.handle_key_event(&press(KeyCode::End));
+        assert_eq!(ff.state.selected(), Some(9));
+        ff.handle_key_event(&press(KeyCode::PageDown));
+        assert_eq!(ff.state.selected(), Some(9));
+        ff.handle_key_event(&press(KeyCode::PageUp));
+        assert_eq!(ff.state.selected(), Some(5));
+        ff.handle_key_event(&press(KeyCode::Home));
+        assert_eq!(ff.state.selected(), Some(0));
+        assert_eq!(ff.handle_key_event(&press(KeyCode::Up)), Change::Unchanged);
+    }
+
+    #[test]
+    fn edit_keys_change_filter() {
+        let mut ff = FuzzyFinder::default().with_options(["ab cd"]);
+        for c in "ab c".chars() {
+            ff.handle_key_event(&press(KeyCode::Char(c)));
+        }
+        assert_eq!(ff.filter(), "ab c");
+        ff.handle_key_event(&KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(ff.filter(), "ab ");
+        ff.handle_key_event(&press(KeyCode::Backspace));
+        assert_eq!(ff.filter(), "ab");
+        let mut release = press(KeyCode::Backspace);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(ff.handle_key_event(&release), Change::Unchanged);
+        assert_eq!(ff.filter(), "ab");
+    }
+
+    #[cfg(feature = "editor")]
+    #[test]
+    fn edit_keys_follow_cursor() {
+        let mut ff = FuzzyFinder::default().with_filter("ac d");
+        ff.move_cursor_left().move_cursor_left().move_cursor_left();
+        ff.handle_key_event(&press(KeyCode::Char('b')));
+        assert_eq!(ff.filter(), "abc d");
+        ff.handle_key_event(&press(KeyCode::Backspace));
+        assert_eq!(ff.filter(), "ac d");
+        ff.move_cursor_right().move_cursor_right();
+        ff.handle_key_event(&KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(ff.filter(), "d");
+        assert_eq!(ff.cursor_column(), 0);
+    }
+}
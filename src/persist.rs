@@ -0,0 +1,66 @@
+use crate::FuzzyFinder;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+
+/// The persistable parts of a `FuzzyFinder`.  Scores are derivable so aren't stored.
+#[derive(Serialize, Deserialize)]
+struct FuzzyFinderState<'a> {
+    filter: Cow<'a, str>,
+    options: Vec<Cow<'a, str>>,
+    selected: Option<usize>,
+}
+
+impl Serialize for FuzzyFinder<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FuzzyFinderState {
+            filter: Cow::Borrowed(self.filter()),
+            options: self
+                .matches
+                .keys()
+                .map(|option| Cow::Borrowed(option.as_ref()))
+                .collect(),
+            selected: self.selected_index(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FuzzyFinder<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let FuzzyFinderState {
+            filter,
+            options,
+            selected,
+        } = FuzzyFinderState::deserialize(deserializer)?;
+        let mut finder = FuzzyFinder::default()
+            .with_filter(filter)
+            .with_options(options);
+        if let Some(selected) = selected {
+            finder.select(selected);
+        }
+        Ok(finder)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut ff = FuzzyFinder::default()
+            .with_filter("o")
+            .with_options(["one", "two", "three"]);
+        ff.select_next();
+        let selection = ff.selection().map(|s| s.value.to_string());
+
+        let json = serde_json::to_string(&ff)?;
+        let restored: FuzzyFinder = serde_json::from_str(&json)?;
+        assert_eq!(restored.filter(), "o");
+        assert_eq!(restored.len(), 3);
+        assert!(restored.matches["three"].score.is_none());
+        assert_eq!(restored.selection().map(|s| s.value.to_string()), selection);
+        Ok(())
+    }
+}
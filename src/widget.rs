@@ -1,11 +1,21 @@
 use crate::{
-    highlight::{sections_from_stringdices, MatchHighlightError, Style as HighlightStyle},
-    FuzzyFinder,
+    highlight::{
+        matched_indices_merged, sections_from_stringdices, MatchHighlightError,
+        Style as HighlightStyle,
+    },
+    FuzzyFinder, FuzzyScore,
 };
+use std::{borrow::Cow, ops::Range};
+use tracing::warn;
 use tui::{
     prelude::*,
-    widgets::{Block, List, ListItem, StatefulWidget},
+    widgets::{Block, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
 };
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const HIGHLIGHT_SYMBOL: &str = "> ";
+const BLANK_GUTTER: &str = "  ";
 
 /// Ephemeral list widget for fuzzy matched items.
 /// Highlights selected line and matched chars.
@@ -23,12 +33,52 @@ use tui::{
 ///     .matched_char_style(Style::default().fg(Color::Cyan))
 ///     .selection_highlight_style(Style::default().add_modifier(Modifier::BOLD));
 /// ```
-#[derive(Default)]
 pub struct FuzzyList<'a> {
     block: Option<Block<'a>>,
+    description_style: Style,
+    ellipsis: Cow<'a, str>,
+    empty_message: Option<Text<'a>>,
+    exact_match_style: Style,
+    group_header_style: Style,
+    gutter: bool,
+    highlight_symbol_style: Style,
+    loading_indicator: Option<Line<'a>>,
+    matched_char_patch: Style,
     matched_char_style: Style,
+    prompt_symbol: Cow<'a, str>,
+    reverse: bool,
     selection_highlight_style: Style,
+    show_prompt: bool,
+    strong_match: Option<(usize, Style)>,
+    top_match_style: Style,
     unmatched_char_style: Style,
+    used_style: Style,
+}
+
+impl Default for FuzzyList<'_> {
+    fn default() -> Self {
+        Self {
+            block: None,
+            description_style: Style::default(),
+            ellipsis: Cow::Borrowed("…"),
+            empty_message: None,
+            exact_match_style: Style::default(),
+            group_header_style: Style::default(),
+            gutter: true,
+            highlight_symbol_style: Style::default(),
+            loading_indicator: None,
+            matched_char_patch: Style::default(),
+            matched_char_style: Style::default(),
+            prompt_symbol: Cow::Borrowed("> "),
+            reverse: false,
+            selection_highlight_style: Style::default(),
+            show_prompt: false,
+            strong_match: None,
+            top_match_style: Style::default(),
+            unmatched_char_style: Style::default(),
+            used_style: Style::default(),
+        }
+    }
 }
 
 impl<'a> FuzzyList<'a> {
@@ -47,6 +97,81 @@ impl<'a> FuzzyList<'a> {
         self
     }
 
+    /// Builder method to set style for option descriptions
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().description_style(Style::default().add_modifier(Modifier::DIM));
+    /// ```
+    pub fn description_style(mut self, style: Style) -> Self {
+        self.description_style = style;
+        self
+    }
+
+    /// Builder method to set the marker appended to options too wide for the list,
+    /// "…" by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().ellipsis("...");
+    /// ```
+    pub fn ellipsis<T: Into<Cow<'a, str>>>(mut self, ellipsis: T) -> Self {
+        self.ellipsis = ellipsis.into();
+        self
+    }
+
+    /// Builder method to set a message shown, centered, when nothing matches the filter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().empty_message("No matches");
+    /// ```
+    pub fn empty_message<T: Into<Text<'a>>>(mut self, message: T) -> Self {
+        self.empty_message = Some(message.into());
+        self
+    }
+
+    /// Builder method to set an indicator shown while the finder is loading, see
+    /// `FuzzyFinder::set_loading`.  It is added to the block's titles, and shown in
+    /// place of the empty message while nothing matches yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().loading_indicator("Loading…");
+    /// ```
+    pub fn loading_indicator<T: Into<Line<'a>>>(mut self, indicator: T) -> Self {
+        self.loading_indicator = Some(indicator.into());
+        self
+    }
+
+    /// Builder method to set style for the row exactly matching the filter
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().exact_match_style(Style::default().fg(Color::Green));
+    /// ```
+    pub fn exact_match_style(mut self, style: Style) -> Self {
+        self.exact_match_style = style;
+        self
+    }
+
     /// Builder method to set style for matched characters in fuzzy search
     ///
     /// # Example
@@ -62,6 +187,119 @@ impl<'a> FuzzyList<'a> {
         self
     }
 
+    /// Builder method to toggle the `> ` gutter marking the selected row.  Without it
+    /// no column is reserved, and selection is shown by `selection_highlight_style` alone.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default()
+    ///     .gutter(false)
+    ///     .selection_highlight_style(Style::default().bg(Color::DarkGray));
+    /// ```
+    pub fn gutter(mut self, gutter: bool) -> Self {
+        self.gutter = gutter;
+        self
+    }
+
+    /// Builder method to set style for the `> ` selection symbol, independently of the
+    /// selected row's text.  It's patched over `selection_highlight_style`, the
+    /// selected row's base style.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default()
+    ///     .highlight_symbol_style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+    ///     .selection_highlight_style(Style::default().add_modifier(Modifier::ITALIC));
+    /// ```
+    pub fn highlight_symbol_style(mut self, style: Style) -> Self {
+        self.highlight_symbol_style = style;
+        self
+    }
+
+    /// Builder method to set a style patched over matched characters on the selected
+    /// row.  Being a patch, modifiers such as `Modifier::UNDERLINED` layer over the
+    /// matched and selection styles, keeping matches visible whatever their colors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default()
+    ///     .matched_char_style(Style::default().fg(Color::Cyan))
+    ///     .matched_char_patch(Style::default().add_modifier(Modifier::UNDERLINED));
+    /// ```
+    pub fn matched_char_patch(mut self, patch: Style) -> Self {
+        self.matched_char_patch = patch;
+        self
+    }
+
+    /// Builder method to set the symbol before the filter in the prompt, "> " by
+    /// default, see `show_prompt`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().show_prompt(true).prompt_symbol("❯ ");
+    /// ```
+    pub fn prompt_symbol<T: Into<Cow<'a, str>>>(mut self, symbol: T) -> Self {
+        self.prompt_symbol = symbol.into();
+        self
+    }
+
+    /// Builder method to title the block with a prompt showing the filter and how
+    /// many options match it, e.g. "> foo (3/120)", kept in sync with the finder on
+    /// every render.  Without a block, the prompt is rendered on the list's top row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().show_prompt(true);
+    /// ```
+    pub fn show_prompt(mut self, show: bool) -> Self {
+        self.show_prompt = show;
+        self
+    }
+
+    /// Builder method to render the best match at the bottom, next to an input box
+    /// below the list, as fzf does.  Only rendering is reversed, so `select_next`
+    /// still moves to the next best match, which is now visually up: bind it to Up,
+    /// and `select_prev` to Down.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().reverse(true);
+    /// ```
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// The corner the list grows from.
+    fn start_corner(&self) -> Corner {
+        if self.reverse {
+            Corner::BottomLeft
+        } else {
+            Corner::TopLeft
+        }
+    }
+
     /// Builder method to set style for selected item in filtered fuzzy list
     ///
     /// # Example
@@ -79,44 +317,844 @@ impl<'a> FuzzyList<'a> {
         self
     }
 
-    fn styled_line(
+    /// Builder method to patch the matched char style with `style` for contiguous
+    /// runs of at least `min_run` matched chars, so they stand out from scattered
+    /// single char matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default()
+    ///     .matched_char_style(Style::default().fg(Color::Cyan))
+    ///     .strong_match_style(3, Style::default().add_modifier(Modifier::BOLD));
+    /// ```
+    pub fn strong_match_style(mut self, min_run: usize, style: Style) -> Self {
+        self.strong_match = Some((min_run, style));
+        self
+    }
+
+    /// Builder method to set style for the header row listed before each group's
+    /// matches, see `FuzzyFinder::push_option_in_group`.  Headers can't be selected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().group_header_style(Style::default().add_modifier(Modifier::BOLD));
+    /// ```
+    pub fn group_header_style(mut self, style: Style) -> Self {
+        self.group_header_style = style;
+        self
+    }
+
+    /// Builder method to patch the matched char style with `style` on the best match,
+    /// drawing attention to it wherever the selection is.  Once selected, the best
+    /// match's chars are styled as any selected row's are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default()
+    ///     .matched_char_style(Style::default().fg(Color::Cyan))
+    ///     .top_match_style(Style::default().add_modifier(Modifier::UNDERLINED));
+    /// ```
+    pub fn top_match_style(mut self, style: Style) -> Self {
+        self.top_match_style = style;
+        self
+    }
+
+    /// Builder method to set style for unmatched characters, empty by default, so they
+    /// keep the terminal's own colors, or the block's if it's styled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().unmatched_char_style(Style::default().fg(Color::Gray));
+    /// ```
+    pub fn unmatched_char_style(mut self, style: Style) -> Self {
+        self.unmatched_char_style = style;
+        self
+    }
+
+    /// Builder method to set style for options marked used, unless selected, see
+    /// `FuzzyFinder::set_used`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::prelude::*;
+    /// use tuiscope::FuzzyList;
+    ///
+    /// let fuzzy = FuzzyList::default().used_style(Style::default().add_modifier(Modifier::DIM));
+    /// ```
+    pub fn used_style(mut self, style: Style) -> Self {
+        self.used_style = style;
+        self
+    }
+
+    /// Renders a frozen snapshot of `finder`'s matches as a plain `List`, for read-only
+    /// UI such as a popup that shouldn't scroll.  The selected row keeps its symbol and
+    /// match patch.  With no area to fit, long options are clipped rather than ellipsized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tui::{backend::TestBackend, Terminal};
+    /// use tuiscope::{FuzzyFinder, FuzzyList};
+    ///
+    /// let finder = FuzzyFinder::default().with_options(["abc", "bcd", "cde"]);
+    /// let fuzzy = FuzzyList::default();
+    /// let mut terminal = Terminal::new(TestBackend::new(10, 3)).unwrap();
+    /// terminal
+    ///     .draw(|f| f.render_widget(fuzzy.snapshot(&finder), f.size()))
+    ///     .unwrap();
+    /// ```
+    pub fn snapshot<'b>(&'b self, finder: &'b FuzzyFinder<'_>) -> List<'b> {
+        let mut list = List::new(self.items(finder, None, 0..finder.matches.len()))
+            .start_corner(self.start_corner());
+        if let Some(block) = self.framing(finder) {
+            list = list.block(block);
+        }
+        list
+    }
+
+    /// The block to draw around the list, titled with the prompt if shown, and the
+    /// loading indicator while `state` is loading.
+    fn framing(&self, state: &FuzzyFinder<'_>) -> Option<Block<'a>> {
+        let mut block = match self.block {
+            Some(ref block) => block.clone(),
+            None if self.show_prompt => Block::default(),
+            None => return None,
+        };
+        if self.show_prompt {
+            block = block.title(format!(
+                "{}{} ({}/{})",
+                self.prompt_symbol,
+                state.filter(),
+                state.matched_len(),
+                state.len()
+            ));
+        }
+        match self.loading_indicator {
+            Some(ref indicator) if state.is_loading() => Some(block.title(indicator.clone())),
+            _ => Some(block),
+        }
+    }
+
+    /// Builds rows for the options in `range` of `state`'s matches which match its
+    /// filter, each truncated to fit `width` columns if given.
+    fn items<'b>(
+        &'b self,
+        state: &'b FuzzyFinder<'_>,
+        width: Option<usize>,
+        range: Range<usize>,
+    ) -> Vec<ListItem<'b>> {
+        let selected = state.selected_index();
+        // like ratatui, only reserve a gutter while something is selected.
+        let gutter = self.gutter && selected.is_some();
+        let width = width.map(|width| {
+            if gutter {
+                width.saturating_sub(HIGHLIGHT_SYMBOL.width())
+            } else {
+                width
+            }
+        });
+        let ellipsis = Span::styled(self.ellipsis.as_ref(), self.unmatched_char_style);
+        let max_spans = state.max_highlight_spans();
+        state
+            .matches
+            .iter()
+            .enumerate()
+            .skip(range.start)
+            .take(range.len())
+            .filter_map(|(i, (value, option))| {
+                let score = option.score.as_ref()?;
+                let header = state.has_header(i, self.reverse).then(|| {
+                    let mut header = Line::from(Span::styled(
+                        option.group.as_deref().unwrap_or_default(),
+                        self.group_header_style,
+                    ));
+                    if let Some(width) = width {
+                        header = truncate_line(header, width, ellipsis.clone());
+                    }
+                    ListItem::new(header)
+                });
+                let is_selected = selected == Some(i);
+                let matched_char_style = if is_selected {
+                    self.matched_char_style.patch(self.matched_char_patch)
+                } else if i == 0 {
+                    self.matched_char_style.patch(self.top_match_style)
+                } else {
+                    self.matched_char_style
+                };
+                let display = option.display(value);
+                let mut line = if option.search_key.is_some() {
+                    // indices refer to the key, not what's displayed.
+                    Line::from(Span::styled(display, self.unmatched_char_style))
+                } else {
+                    self.styled_field(display, 0, score, matched_char_style, max_spans)
+                };
+                if !option.styles.is_empty() {
+                    line = with_base_styles(line, &option.styles);
+                }
+                for (field, text) in option.fields.iter().enumerate() {
+                    line.spans.push(Span::raw(" "));
+                    let styled =
+                        self.styled_field(text, field + 1, score, matched_char_style, max_spans);
+                    line.spans.extend(styled.spans);
+                }
+                if let Some(ref description) = option.description {
+                    line.spans.push(Span::raw(" "));
+                    line.spans
+                        .push(Span::styled(description.as_ref(), self.description_style));
+                }
+                if let Some(ref icon) = option.icon {
+                    line.spans.insert(0, icon.clone());
+                }
+                if let Some(width) = width {
+                    line = truncate_line(line, width, ellipsis.clone());
+                }
+                if gutter {
+                    // the symbol is a span of its own, so it can be styled separately.
+                    let symbol = if is_selected {
+                        Span::styled(HIGHLIGHT_SYMBOL, self.highlight_symbol_style)
+                    } else {
+                        Span::raw(BLANK_GUTTER)
+                    };
+                    line.spans.insert(0, symbol);
+                }
+                let mut style = Style::default();
+                if option.used && !is_selected {
+                    style = style.patch(self.used_style);
+                }
+                if state.is_exact_match(display) {
+                    style = style.patch(self.exact_match_style);
+                }
+                if is_selected {
+                    // the row's base style, so matched chars' own styles patch over it,
+                    // e.g. keeping their background on a highlighted row.
+                    style = style.patch(self.selection_highlight_style);
+                }
+                Some((header, ListItem::new(line).style(style)))
+            })
+            .flat_map(|(header, item)| {
+                // a reversed list is drawn bottom up, so its headers go after their group.
+                if self.reverse {
+                    [Some(item), header]
+                } else {
+                    [header, Some(item)]
+                }
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Line for field number `field` of an option, highlighting matched chars if
+    /// `score` is for that field, as one envelope if in more than `max_spans` runs.
+    fn styled_field<'b>(
         &self,
-        value: &'a str,
-        indices: &'a [usize],
-    ) -> Result<Line, MatchHighlightError> {
+        text: &'b str,
+        field: usize,
+        score: &'b FuzzyScore,
+        matched_char_style: Style,
+        max_spans: Option<usize>,
+    ) -> Line<'b> {
+        let mut indices: Vec<usize> = if score.field == field {
+            // indices past a truncated option's cut aren't displayed.
+            let len = text.chars().count();
+            score.indices.iter().copied().filter(|&i| i < len).collect()
+        } else {
+            Vec::new()
+        };
+        if let Some(max_spans) = max_spans {
+            let runs = matched_indices_merged(&indices);
+            if runs.len() > max_spans {
+                // runs are sorted, so span from the first's start to the last's end.
+                indices = (runs[0].0..runs[runs.len() - 1].1).collect();
+            }
+        }
+        // a row which can't be highlighted is still better than a missing row.
+        self.styled_line(text, &indices, matched_char_style)
+            .unwrap_or_else(|error| {
+                warn!("Unable to highlight `{text}`: {error}");
+                Line::from(Span::styled(text, self.unmatched_char_style))
+            })
+    }
+
+    fn styled_line<'b>(
+        &self,
+        value: &'b str,
+        indices: &[usize],
+        matched_char_style: Style,
+    ) -> Result<Line<'b>, MatchHighlightError> {
         Ok(Line::from(
             sections_from_stringdices(value, indices)?
                 .iter()
                 .map(|section| match section {
                     HighlightStyle::None(sub) => Span::styled(*sub, self.unmatched_char_style),
-                    HighlightStyle::Matched(sub) => Span::styled(*sub, self.matched_char_style),
+                    HighlightStyle::Matched(sub) => match self.strong_match {
+                        Some((min_run, strong)) if sub.graphemes(true).count() >= min_run => {
+                            Span::styled(*sub, matched_char_style.patch(strong))
+                        }
+                        _ => Span::styled(*sub, matched_char_style),
+                    },
                 })
                 .collect::<Vec<Span>>(),
         ))
     }
+
+    /// Renders `message` centered within the block, in place of an empty list.
+    fn render_empty_message(
+        &self,
+        message: Text<'a>,
+        block: Option<Block<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let inner = match block {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+        let height = u16::try_from(message.height())
+            .unwrap_or(u16::MAX)
+            .min(inner.height);
+        let message_area = Rect {
+            y: inner.y + (inner.height - height) / 2,
+            height,
+            ..inner
+        };
+        Paragraph::new(message)
+            .style(self.unmatched_char_style)
+            .alignment(Alignment::Center)
+            .render(message_area, buf);
+    }
 }
 
 impl<'a> StatefulWidget for FuzzyList<'a> {
     type State = FuzzyFinder<'a>;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let list: Vec<ListItem> = state
-            .matches
+        let block = self.framing(state);
+        if !state.has_matches() {
+            let message = match self.loading_indicator {
+                Some(ref indicator) if state.is_loading() => Some(Text::from(indicator.clone())),
+                _ => self.empty_message.clone(),
+            };
+            if let Some(message) = message {
+                self.render_empty_message(message, block, area, buf);
+                return;
+            }
+        }
+        let inner = block.as_ref().map_or(area, |block| block.inner(area));
+        // only build rows in view, scrolled as `visible_range` describes.
+        let range = state.scrolled_range(usize::from(inner.height), self.reverse);
+        state.set_page_len(usize::from(inner.height));
+        let list = self.items(state, Some(usize::from(inner.width)), range.clone());
+        let mut list = List::new(list).start_corner(self.start_corner());
+        if let Some(block) = block {
+            list = list.block(block);
+        }
+        let mut list_state = ListState::default();
+        list_state.select(state.selected_index().map(|selected| {
+            let selected = selected.min(range.end.saturating_sub(1));
+            // header rows come before the selected option's row too, or after it
+            // when reversed.
+            let before = if self.reverse { selected } else { selected + 1 };
+            let headers = (range.start..before)
+                .filter(|&i| state.has_header(i, self.reverse))
+                .count();
+            selected - range.start + headers
+        }));
+        StatefulWidget::render(list, area, buf, &mut list_state);
+        *state.state.offset_mut() = range.start;
+    }
+}
+
+/// Splits `line`'s spans where the base style changes, per `styles` giving the
+/// style from each char index on, and patches each span's style over its base.
+fn with_base_styles<'b>(line: Line<'b>, styles: &[(usize, Style)]) -> Line<'b> {
+    let mut styles = styles.iter().peekable();
+    let mut base = Style::default();
+    let mut char_index = 0;
+    let mut spans = Vec::with_capacity(line.spans.len());
+    for span in line.spans {
+        let mut start = 0;
+        for (offset, _) in span.content.char_indices() {
+            while let Some((_, style)) = styles.next_if(|(from, _)| *from <= char_index) {
+                if offset > start {
+                    let piece = slice_cow(&span.content, start..offset);
+                    spans.push(Span::styled(piece, base.patch(span.style)));
+                    start = offset;
+                }
+                base = *style;
+            }
+            char_index += 1;
+        }
+        if start < span.content.len() {
+            let piece = slice_cow(&span.content, start..span.content.len());
+            spans.push(Span::styled(piece, base.patch(span.style)));
+        }
+    }
+    Line::from(spans)
+}
+
+/// The `range` of `text`, still borrowed if `text` is.
+fn slice_cow<'b>(text: &Cow<'b, str>, range: Range<usize>) -> Cow<'b, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(&text[range]),
+        Cow::Owned(text) => Cow::Owned(text[range].to_string()),
+    }
+}
+
+/// Truncates `line` to fit within `width` columns, ending it with `ellipsis` when
+/// anything is cut.  Spans wholly past the cut are dropped.
+fn truncate_line<'b>(line: Line<'b>, width: usize, ellipsis: Span<'b>) -> Line<'b> {
+    if line.width() <= width {
+        return line;
+    }
+    let available = width.saturating_sub(ellipsis.width());
+    let mut used = 0;
+    let mut spans = Vec::new();
+    for span in line.spans {
+        let span_width = span.width();
+        if used + span_width <= available {
+            used += span_width;
+            spans.push(span);
+            continue;
+        }
+        let mut end = 0;
+        for (offset, grapheme) in span.content.grapheme_indices(true) {
+            used += grapheme.width();
+            if used > available {
+                break;
+            }
+            end = offset + grapheme.len();
+        }
+        if end > 0 {
+            let content = match span.content {
+                Cow::Borrowed(content) => Cow::Borrowed(&content[..end]),
+                Cow::Owned(mut content) => {
+                    content.truncate(end);
+                    Cow::Owned(content)
+                }
+            };
+            spans.push(Span::styled(content, span.style));
+        }
+        break;
+    }
+    if ellipsis.width() <= width {
+        spans.push(ellipsis);
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Sanitizer;
+    use tui::{backend::TestBackend, widgets::Borders};
+
+    fn contents(line: &Line) -> Vec<String> {
+        line.spans
             .iter()
-            .filter_map(|(value, score)| {
-                score
-                    .as_ref()
-                    .and_then(|score| self.styled_line(value, &score.indices).ok())
-            })
-            .take(area.height as usize + state.state.selected().unwrap_or(0))
-            .map(ListItem::new)
+            .map(|span| span.content.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_renders_without_state() {
+        let finder = FuzzyFinder::default().with_options(["abc", "abd"]);
+        let fuzzy = FuzzyList::default();
+        let area = Rect::new(0, 0, 6, 2);
+        let mut buf = Buffer::empty(area);
+        Widget::render(fuzzy.snapshot(&finder), area, &mut buf);
+        let symbols: String = buf
+            .content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
             .collect();
-        let mut list = List::new(list)
-            .highlight_style(self.selection_highlight_style)
-            .highlight_symbol("> ");
-        if let Some(ref block) = self.block {
-            list = list.block(block.clone());
+        assert_eq!(symbols, "> abc   abd ");
+    }
+
+    #[test]
+    fn matched_field_is_highlighted() {
+        let mut finder = FuzzyFinder::default();
+        finder.push_option_fields(["ab", "cd"]);
+        finder.set_filter("d");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().fg(Color::Cyan));
+        let area = Rect::new(0, 0, 5, 1);
+        let mut buf = Buffer::empty(area);
+        Widget::render(fuzzy.snapshot(&finder), area, &mut buf);
+        let mut expected = Buffer::with_lines(vec!["ab cd"]);
+        expected.get_mut(4, 0).set_fg(Color::Cyan);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn icons_are_not_highlighted() {
+        let mut finder = FuzzyFinder::default();
+        finder.push_option_with_icon("ab", Span::styled("a ", Style::default().fg(Color::Red)));
+        finder.set_filter("a");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().fg(Color::Cyan));
+        let area = Rect::new(0, 0, 4, 1);
+        let mut buf = Buffer::empty(area);
+        Widget::render(fuzzy.snapshot(&finder), area, &mut buf);
+        let mut expected = Buffer::with_lines(vec!["a ab"]);
+        expected.get_mut(0, 0).set_fg(Color::Red);
+        expected.get_mut(1, 0).set_fg(Color::Red);
+        expected.get_mut(2, 0).set_fg(Color::Cyan);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn deep_selection_is_scrolled_into_view() {
+        let options: Vec<_> = (0..100).map(|i| format!("{i:02}")).collect();
+        let mut finder = FuzzyFinder::default();
+        finder.push_options(&options);
+        for _ in 0..50 {
+            finder.select_next();
+        }
+        let area = Rect::new(0, 0, 4, 3);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(FuzzyList::default(), area, &mut buf, &mut finder);
+        assert_eq!(buf, Buffer::with_lines(vec!["  48", "  49", "> 50"]));
+        assert_eq!(finder.state.offset(), 48);
+        finder.select_prev().select_prev().select_prev();
+        StatefulWidget::render(FuzzyList::default(), area, &mut buf, &mut finder);
+        assert_eq!(buf, Buffer::with_lines(vec!["> 47", "  48", "  49"]));
+    }
+
+    #[test]
+    fn loading_indicator_is_shown_while_loading() {
+        let mut finder = FuzzyFinder::default();
+        finder.set_loading(true);
+        let fuzzy = || {
+            FuzzyList::default()
+                .empty_message("none")
+                .loading_indicator("wait")
+        };
+        let area = Rect::new(0, 0, 4, 1);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(fuzzy(), area, &mut buf, &mut finder);
+        assert_eq!(buf, Buffer::with_lines(vec!["wait"]));
+        finder.set_loading(false);
+        StatefulWidget::render(fuzzy(), area, &mut buf, &mut finder);
+        assert_eq!(buf, Buffer::with_lines(vec!["none"]));
+    }
+
+    #[test]
+    fn loading_indicator_titles_block() {
+        let mut finder = FuzzyFinder::default().with_options(["ab"]);
+        finder.set_loading(true);
+        let fuzzy = FuzzyList::default()
+            .block(Block::default().borders(Borders::TOP))
+            .loading_indicator("wait");
+        let area = Rect::new(0, 0, 4, 2);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(fuzzy, area, &mut buf, &mut finder);
+        assert_eq!(buf, Buffer::with_lines(vec!["wait", "> ab"]));
+    }
+
+    #[test]
+    fn used_options_are_dimmed_unless_selected() {
+        let mut finder = FuzzyFinder::default().with_options(["ab", "cd"]);
+        finder.set_used("ab", true).set_used("cd", true);
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .used_style(Style::default().add_modifier(Modifier::DIM));
+        let area = Rect::new(0, 0, 2, 2);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(fuzzy, area, &mut buf, &mut finder);
+        let mut expected = Buffer::with_lines(vec!["ab", "cd"]);
+        expected.set_style(
+            Rect::new(0, 1, 2, 1),
+            Style::default().add_modifier(Modifier::DIM),
+        );
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn long_runs_are_strong() {
+        let mut finder = FuzzyFinder::default().with_options(["abxcd"]);
+        finder.set_filter("acd");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().fg(Color::Cyan))
+            .strong_match_style(2, Style::default().add_modifier(Modifier::BOLD));
+        let area = Rect::new(0, 0, 5, 1);
+        let mut buf = Buffer::empty(area);
+        Widget::render(fuzzy.snapshot(&finder), area, &mut buf);
+        let mut expected = Buffer::with_lines(vec!["abxcd"]);
+        expected.get_mut(0, 0).set_fg(Color::Cyan);
+        for x in 3..5 {
+            expected
+                .get_mut(x, 0)
+                .set_fg(Color::Cyan)
+                .set_style(Style::default().add_modifier(Modifier::BOLD));
         }
-        StatefulWidget::render(list, area, buf, &mut state.state);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn reversed_list_renders_best_match_at_bottom() {
+        let mut finder = FuzzyFinder::default().with_options(["a", "b", "c", "d"]);
+        let area = Rect::new(0, 0, 3, 3);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(
+            FuzzyList::default().reverse(true),
+            area,
+            &mut buf,
+            &mut finder,
+        );
+        assert_eq!(buf, Buffer::with_lines(vec!["  c", "  b", "> a"]));
+        finder.select_next().select_next().select_next();
+        StatefulWidget::render(
+            FuzzyList::default().reverse(true),
+            area,
+            &mut buf,
+            &mut finder,
+        );
+        assert_eq!(buf, Buffer::with_lines(vec!["> d", "  c", "  b"]));
+    }
+
+    #[test]
+    fn matched_background_layers_over_selection() {
+        let mut finder = FuzzyFinder::default().with_options(["ab", "cb"]);
+        finder.set_filter("b");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().bg(Color::Yellow))
+            .selection_highlight_style(Style::default().bg(Color::Blue));
+        let mut terminal = Terminal::new(TestBackend::new(2, 2)).unwrap();
+        terminal
+            .draw(|f| f.render_stateful_widget(fuzzy, f.size(), &mut finder))
+            .unwrap();
+        let mut expected = Buffer::with_lines(vec!["ab", "cb"]);
+        expected.get_mut(0, 0).set_bg(Color::Blue);
+        expected.get_mut(1, 0).set_bg(Color::Yellow);
+        expected.get_mut(1, 1).set_bg(Color::Yellow);
+        terminal.backend().assert_buffer(&expected);
+    }
+
+    fn draw<'a>(
+        fuzzy: FuzzyList<'a>,
+        finder: &mut FuzzyFinder<'a>,
+        width: u16,
+        height: u16,
+    ) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| f.render_stateful_widget(fuzzy, f.size(), finder))
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    #[test]
+    fn renders_ranked_rows_with_gutter() {
+        let mut finder = FuzzyFinder::default().with_options(["xaxb", "zzz", "abx"]);
+        finder.set_filter("ab");
+        let fuzzy = FuzzyList::default().matched_char_style(Style::default().fg(Color::Cyan));
+        let buf = draw(fuzzy, &mut finder, 6, 3);
+        let mut expected = Buffer::with_lines(vec!["> abx ", "  xaxb", "      "]);
+        for (x, y) in [(2, 0), (3, 0), (3, 1), (5, 1)] {
+            expected.get_mut(x, y).set_fg(Color::Cyan);
+        }
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn unmatched_chars_keep_terminal_colors() {
+        let mut finder = FuzzyFinder::default().with_options(["abc", "xbz"]);
+        finder.set_filter("b");
+        let fuzzy = FuzzyList::default().matched_char_style(Style::default().fg(Color::Cyan));
+        let buf = draw(fuzzy, &mut finder, 5, 2);
+        for (x, y) in [(2, 0), (4, 0), (2, 1), (4, 1)] {
+            let cell = buf.get(x, y);
+            assert_eq!((cell.fg, cell.bg), (Color::Reset, Color::Reset));
+        }
+        assert_eq!(buf.get(3, 0).fg, Color::Cyan);
+    }
+
+    #[test]
+    fn group_headers_count_towards_scrolling() {
+        let mut finder = FuzzyFinder::default();
+        for (option, group) in [("a1", "A"), ("a2", "A"), ("b1", "B"), ("b2", "B")] {
+            finder.push_option_in_group(option, group);
+        }
+        finder.select(3);
+        assert_eq!(finder.visible_range(3), 2..4);
+        let buf = draw(FuzzyList::default(), &mut finder, 4, 3);
+        assert_eq!(buf, Buffer::with_lines(vec!["B   ", "  b1", "> b2"]));
+        assert_eq!(finder.state.offset(), 2);
+    }
+
+    #[test]
+    fn renders_truncated_rows() {
+        let mut finder = FuzzyFinder::default().with_options(["abcdefgh", "ab"]);
+        let buf = draw(FuzzyList::default(), &mut finder, 6, 2);
+        assert_eq!(buf, Buffer::with_lines(vec!["> abc…", "  ab  "]));
+    }
+
+    #[test]
+    fn group_headers_precede_their_matches() {
+        let mut finder = FuzzyFinder::default();
+        finder.push_option_in_group("fetch", "Git");
+        finder.push_option_in_group("f", "File");
+        finder.set_filter("f");
+        finder.select_next();
+        let fuzzy = FuzzyList::default();
+        let buf = draw(fuzzy, &mut finder, 8, 4);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(vec!["Git     ", "  fetch ", "File    ", "> f     "])
+        );
+    }
+
+    #[test]
+    fn reversed_group_headers_sit_above_their_matches() {
+        let mut finder = FuzzyFinder::default();
+        for (option, group) in [("a1", "A"), ("a2", "A"), ("b1", "B"), ("b2", "B")] {
+            finder.push_option_in_group(option, group);
+        }
+        let fuzzy = FuzzyList::default().reverse(true);
+        let buf = draw(fuzzy, &mut finder, 4, 6);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(vec!["B   ", "  b2", "  b1", "A   ", "  a2", "> a1"])
+        );
+        finder.select(2);
+        let buf = draw(FuzzyList::default().reverse(true), &mut finder, 4, 3);
+        assert_eq!(buf, Buffer::with_lines(vec!["> b1", "A   ", "  a2"]));
+    }
+
+    #[test]
+    fn scattered_matches_are_highlighted_as_envelope() {
+        let mut finder = FuzzyFinder::default();
+        finder.set_max_highlight_spans(1);
+        finder.push_option("axbxc");
+        finder.set_filter("abc");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().fg(Color::Cyan));
+        let buf = draw(fuzzy, &mut finder, 5, 1);
+        let mut expected = Buffer::with_lines(vec!["axbxc"]);
+        for x in 0..5 {
+            expected.get_mut(x, 0).set_fg(Color::Cyan);
+        }
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn ansi_colors_are_kept_under_matched_style() {
+        let mut finder = FuzzyFinder::default();
+        finder.set_sanitizer(Sanitizer::AnsiStyled);
+        finder.push_option("\x1b[31mab\x1b[0mc");
+        finder.set_filter("b");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().add_modifier(Modifier::BOLD));
+        let buf = draw(fuzzy, &mut finder, 3, 1);
+        let mut expected = Buffer::with_lines(vec!["abc"]);
+        expected.get_mut(0, 0).set_fg(Color::Red);
+        expected
+            .get_mut(1, 0)
+            .set_fg(Color::Red)
+            .set_style(Style::default().add_modifier(Modifier::BOLD));
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn prompt_shows_filter_and_counts() {
+        let mut finder = FuzzyFinder::default().with_options(["abc", "abd", "xyz"]);
+        finder.set_filter("ab");
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .show_prompt(true)
+            .prompt_symbol("$ ");
+        let buf = draw(fuzzy, &mut finder, 12, 3);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(vec!["$ ab (2/3)  ", "abc         ", "abd         "])
+        );
+    }
+
+    #[test]
+    fn top_match_is_styled_unless_selected() {
+        let mut finder = FuzzyFinder::default().with_options(["ab", "axb"]);
+        finder.set_filter("ab");
+        let underlined = Style::default().add_modifier(Modifier::UNDERLINED);
+        let fuzzy = || {
+            FuzzyList::default()
+                .gutter(false)
+                .top_match_style(underlined)
+        };
+        let buf = draw(fuzzy(), &mut finder, 3, 2);
+        assert_eq!(buf, Buffer::with_lines(vec!["ab ", "axb"]));
+        finder.select_next();
+        let buf = draw(fuzzy(), &mut finder, 3, 2);
+        let mut expected = Buffer::with_lines(vec!["ab ", "axb"]);
+        expected.set_style(Rect::new(0, 0, 2, 1), underlined);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn indices_past_truncation_are_ignored() {
+        let mut finder = FuzzyFinder::default();
+        finder.set_max_option_len(3);
+        finder.push_option("abcdef");
+        finder.matches[0].score = Some(FuzzyScore {
+            score: 1,
+            indices: vec![0, 5],
+            field: 0,
+            quality: 0.0,
+        });
+        let fuzzy = FuzzyList::default()
+            .gutter(false)
+            .matched_char_style(Style::default().fg(Color::Cyan));
+        let buf = draw(fuzzy, &mut finder, 3, 1);
+        let mut expected = Buffer::with_lines(vec!["ab…"]);
+        expected.get_mut(0, 0).set_fg(Color::Cyan);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn short_lines_are_untouched() {
+        let line = truncate_line(Line::from("abc"), 3, Span::raw("…"));
+        assert_eq!(contents(&line), vec!["abc"]);
+    }
+
+    #[test]
+    fn truncate_drops_spans_past_the_cut() {
+        let line = Line::from(vec![Span::raw("ab"), Span::raw("cdef"), Span::raw("ghi")]);
+        let line = truncate_line(line, 4, Span::raw("…"));
+        assert_eq!(contents(&line), vec!["ab", "c", "…"]);
+    }
+
+    #[test]
+    fn truncate_wide_chars_by_column() {
+        let line = truncate_line(Line::from("日本語テキスト"), 7, Span::raw("…"));
+        assert_eq!(contents(&line), vec!["日本語", "…"]);
+        assert_eq!(line.width(), 7);
+        let line = truncate_line(Line::from("日本語テキスト"), 8, Span::raw("…"));
+        assert_eq!(contents(&line), vec!["日本語", "…"]);
     }
 }